@@ -0,0 +1,207 @@
+//! Solving many seeds at once on a thread pool. Each seed gets its own solver and
+//! random number generator, so its tiling is the same however many threads run the
+//! batch; only the timings differ.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::Serialize;
+
+use crate::icosahedron::Icosahedron;
+use crate::tileset::DeltilleOptions;
+use crate::wave_function_collapse::{WfcConfig, WfcState};
+
+/// How one seed went
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeedReport {
+    pub seed: u64,
+
+    /// the tiling was solved and its outputs written
+    pub success: bool,
+
+    /// why not, for failed seeds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// files written for the seed
+    pub outputs: Vec<PathBuf>,
+
+    /// over every attempt
+    pub backtracks: usize,
+    pub restarts: usize,
+    pub contradictions: usize,
+
+    /// solving and writing the outputs
+    pub seconds: f64,
+}
+
+/// What a batch did, with its seeds in order; written out as the manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchReport {
+    pub frequency: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+
+    /// wall time of the whole batch
+    pub seconds: f64,
+    pub seeds: Vec<SeedReport>,
+}
+
+impl BatchReport {
+    /// Writes the report as pretty-printed JSON
+    pub fn write_manifest(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("batch reports serialize");
+        return fs::write(path, json);
+    }
+}
+
+/// Solves every seed in `seeds` with `config` otherwise, on `threads` threads or one per
+/// core if 0, and hands each solved tiling to `write`, which returns the files it wrote.
+/// Failed solves and failed writes are reported rather than stopping the batch.
+pub fn run_batch<F, E>(
+    deltille_options: &DeltilleOptions,
+    config: WfcConfig,
+    seeds: RangeInclusive<u64>,
+    threads: usize,
+    write: F,
+) -> io::Result<BatchReport>
+where
+    F: Fn(&WfcState) -> Result<Vec<PathBuf>, E> + Sync,
+    E: fmt::Display,
+{
+    let start = Instant::now();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(io::Error::other)?;
+    // the slots and their neighbors are the same for every seed
+    let icosahedron = Arc::new(Icosahedron::with_layout(config.frequency, config.layout));
+    let seeds: Vec<SeedReport> = pool.install(|| {
+        return seeds
+            .into_par_iter()
+            .map(|seed| {
+                let config = WfcConfig { seed, ..config };
+                return solve_seed(&icosahedron, deltille_options, config, &write);
+            })
+            .collect();
+    });
+
+    let succeeded = seeds.iter().filter(|report| report.success).count();
+    return Ok(BatchReport {
+        frequency: config.frequency,
+        succeeded,
+        failed: seeds.len() - succeeded,
+        seconds: start.elapsed().as_secs_f64(),
+        seeds,
+    });
+}
+
+fn solve_seed<F, E>(
+    icosahedron: &Arc<Icosahedron>,
+    deltille_options: &DeltilleOptions,
+    config: WfcConfig,
+    write: &F,
+) -> SeedReport
+where
+    F: Fn(&WfcState) -> Result<Vec<PathBuf>, E>,
+    E: fmt::Display,
+{
+    let start = Instant::now();
+    let mut state =
+        WfcState::with_icosahedron(Arc::clone(icosahedron), deltille_options.clone(), config);
+    let result = match state.run() {
+        Ok(()) => write(&state).map_err(|error| error.to_string()),
+        Err(error) => Err(error.to_string()),
+    };
+    let (outputs, error) = match result {
+        Ok(outputs) => (outputs, None),
+        Err(error) => (Vec::new(), Some(error)),
+    };
+    return SeedReport {
+        seed: config.seed,
+        success: error.is_none(),
+        error,
+        outputs,
+        backtracks: state.total_backtracks,
+        restarts: state.restarts,
+        contradictions: state.contradictions,
+        seconds: start.elapsed().as_secs_f64(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use serde_json::Value;
+
+    use crate::config_constants::DELTILLES_PATH;
+    use crate::tileset::Tileset;
+    use crate::wave_function_collapse::WfcConfig;
+    use crate::Domain;
+
+    use super::run_batch;
+
+    #[test]
+    fn seeds_solve_the_same_on_any_number_of_threads() {
+        let deltille_options = Tileset::load(DELTILLES_PATH).unwrap().deltille_options();
+        let config = WfcConfig {
+            frequency: 3,
+            ..WfcConfig::default()
+        };
+        let solve = |threads| {
+            let tilings: Mutex<Vec<(u64, Vec<Domain>)>> = Mutex::new(Vec::new());
+            let report = run_batch(&deltille_options, config, 10..=15, threads, |state| {
+                let tiling = (state.config.seed, state.domains.clone());
+                tilings.lock().unwrap().push(tiling);
+                return Ok::<_, String>(Vec::new());
+            })
+            .unwrap();
+            let mut tilings = tilings.into_inner().unwrap();
+            tilings.sort_by_key(|(seed, _)| *seed);
+            return (report, tilings);
+        };
+
+        let (report, one_thread) = solve(1);
+        let (_, four_threads) = solve(4);
+        assert_eq!(one_thread, four_threads);
+        assert_eq!(report.succeeded, 6);
+        let seeds: Vec<u64> = report.seeds.iter().map(|seed| seed.seed).collect();
+        assert_eq!(seeds, (10..=15).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn failed_writes_are_reported_in_the_manifest() {
+        let deltille_options = Tileset::load(DELTILLES_PATH).unwrap().deltille_options();
+        let config = WfcConfig {
+            frequency: 2,
+            ..WfcConfig::default()
+        };
+        let report = run_batch(&deltille_options, config, 0..=2, 0, |state| {
+            if state.config.seed == 1 {
+                return Err("disk full");
+            }
+            return Ok(vec![PathBuf::from(format!("{}.png", state.config.seed))]);
+        })
+        .unwrap();
+        assert_eq!((report.succeeded, report.failed), (2, 1));
+
+        let path = std::env::temp_dir().join(format!("ico_manifest_{}.json", std::process::id()));
+        report.write_manifest(&path).unwrap();
+        let manifest: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(manifest["failed"], 1);
+        assert_eq!(manifest["seeds"][0]["outputs"][0], "0.png");
+        assert_eq!(manifest["seeds"][1]["error"], "disk full");
+        assert!(manifest["seeds"][2].get("error").is_none());
+        assert!(manifest["seeds"][2]["contradictions"].is_u64());
+    }
+}
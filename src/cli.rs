@@ -0,0 +1,734 @@
+//! Command line interface. Only `view` starts Bevy and opens a window; `generate` and
+//! `batch` run headless, so they work on machines without a display.
+//!
+//! ```text
+//! ico_generator generate --tileset assets/deltilles.json --seed 42 --out planet.png
+//! ico_generator generate --seed 42 --out planet.png --mesh-out planet.glb --tile-ids
+//! ico_generator generate --seed 42 --out planet.png --projection-out sky.png --projection cubemap
+//! ico_generator batch --count 100 --out-dir generated/ --threads 8
+//! ico_generator view --seed 42
+//! ico_generator view --seed 42 --paused --steps-per-frame 16
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::builder::TypedValueParser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use ico_generator::batch::run_batch;
+use ico_generator::config_constants::DELTILLES_PATH;
+use ico_generator::export::{export_net, ExportError};
+use ico_generator::mesh::MeshDetail;
+use ico_generator::mesh_export::{export_mesh, MeshExportOptions, TextureMode};
+use ico_generator::net_layout::NetLayout;
+use ico_generator::projection::{export_projection, Projection, Sampling};
+use ico_generator::{DeltilleOptions, Tileset, WfcConfig, WfcState};
+
+#[derive(Parser, Debug)]
+#[command(about = "Generates deltille tilings of an icosphere")]
+pub struct Cli {
+    /// defaults to `view`
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Solve one tiling and write the net as an image
+    Generate {
+        #[command(flatten)]
+        solver: SolverArgs,
+
+        /// seed for the solver, random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// output image, e.g. planet.png
+        #[arg(long)]
+        out: PathBuf,
+
+        #[command(flatten)]
+        mesh: MeshOutArgs,
+
+        #[command(flatten)]
+        projection: ProjectionArgs,
+    },
+
+    /// Solve tilings for consecutive seeds in parallel and write each net into a
+    /// directory, with a `manifest.json` of how every seed went
+    Batch {
+        #[command(flatten)]
+        solver: SolverArgs,
+
+        /// number of tilings
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        count: u64,
+
+        /// seed of the first tiling; the rest count up from it
+        #[arg(long, default_value_t = 0)]
+        first_seed: u64,
+
+        /// directory for the images, named `<seed>.png`
+        #[arg(long)]
+        out_dir: PathBuf,
+
+        /// solver threads, one per core if 0
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+
+    /// Open a window and watch a tiling being solved
+    View {
+        #[command(flatten)]
+        solver: SolverArgs,
+
+        /// seed for the solver, random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// show the net wrapped around the icosahedron instead of flat
+        #[arg(long = "3d")]
+        three_d: bool,
+
+        /// mesh for the 3D view
+        #[arg(long, value_enum, default_value_t = MeshArg::Icofaces)]
+        mesh: MeshArg,
+
+        #[command(flatten)]
+        playback: PlaybackArgs,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PlaybackArgs {
+    /// solver steps per frame; up and down double and halve it
+    #[arg(long, default_value_t = 1)]
+    pub steps_per_frame: usize,
+
+    /// start paused; space resumes and the right arrow takes one step
+    #[arg(long)]
+    pub paused: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshArg {
+    /// one flat triangle per icoface
+    Icofaces,
+
+    /// one flat triangle per deltille
+    Deltilles,
+
+    /// one triangle per deltille, pushed out onto a sphere
+    Sphere,
+}
+
+impl From<MeshArg> for MeshDetail {
+    fn from(mesh: MeshArg) -> Self {
+        return match mesh {
+            MeshArg::Icofaces => MeshDetail::Icofaces,
+            MeshArg::Deltilles => MeshDetail::Deltilles,
+            MeshArg::Sphere => MeshDetail::Sphere,
+        };
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct MeshOutArgs {
+    /// also write the icosphere as a textured mesh, e.g. planet.glb or planet.obj
+    #[arg(long)]
+    pub mesh_out: Option<PathBuf>,
+
+    /// triangles of the exported mesh
+    #[arg(long = "mesh-detail", value_enum, default_value_t = MeshArg::Deltilles)]
+    pub detail: MeshArg,
+
+    /// give each triangle the id of its tile
+    #[arg(long)]
+    pub tile_ids: bool,
+
+    /// write a .glb's texture as a .png beside it instead of embedding it
+    #[arg(long)]
+    pub reference_texture: bool,
+}
+
+impl MeshOutArgs {
+    /// The export options, with `gutter` pixels of bleed in the texture
+    pub fn options(&self, gutter: usize) -> MeshExportOptions {
+        return MeshExportOptions {
+            detail: self.detail.into(),
+            tile_ids: self.tile_ids,
+            texture: if self.reference_texture {
+                TextureMode::Referenced
+            } else {
+                TextureMode::Embedded
+            },
+            gutter,
+        };
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProjectionArgs {
+    /// also write the tiling resampled into a lat/long image or cubemap, e.g. sky.png
+    #[arg(long)]
+    pub projection_out: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = ProjectionArg::Equirectangular)]
+    pub projection: ProjectionArg,
+
+    /// height of the lat/long image, which is twice as wide, or the cubemap face size
+    #[arg(long, default_value_t = 512)]
+    pub projection_size: u32,
+
+    /// blend neighbouring pixels instead of keeping the nearest one
+    #[arg(long)]
+    pub bilinear: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionArg {
+    /// one image, longitude across and latitude down
+    Equirectangular,
+
+    /// six images named `<name>_px.png`, `<name>_nx.png` and so on
+    Cubemap,
+}
+
+impl From<ProjectionArg> for Projection {
+    fn from(projection: ProjectionArg) -> Self {
+        return match projection {
+            ProjectionArg::Equirectangular => Projection::Equirectangular,
+            ProjectionArg::Cubemap => Projection::Cubemap,
+        };
+    }
+}
+
+impl ProjectionArgs {
+    pub fn sampling(&self) -> Sampling {
+        if self.bilinear {
+            return Sampling::Bilinear;
+        }
+        return Sampling::Nearest;
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutArg {
+    /// five columns of staggered rows
+    Standard,
+
+    /// the standard net with the south cap in the gaps of the north cap
+    TwoStrip,
+
+    /// every icoface in its own padded cell of a 5 × 4 grid
+    Atlas,
+
+    /// an irregular unfolding in the style of Fuller's Dymaxion map
+    Dymaxion,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SolverArgs {
+    /// tileset file; deltille image paths are relative to its directory
+    #[arg(long, default_value = DELTILLES_PATH)]
+    pub tileset: PathBuf,
+
+    /// deltilles along each icoface edge, at least 1
+    #[arg(
+        long,
+        default_value_t = WfcConfig::default().frequency,
+        value_parser = clap::value_parser!(u64).range(1..).map(|frequency| frequency as usize),
+    )]
+    pub frequency: usize,
+
+    /// how the icofaces are laid out in the net image
+    #[arg(long, value_enum, default_value_t = LayoutArg::Standard)]
+    pub layout: LayoutArg,
+
+    /// pixels around each icoface in the atlas layout
+    #[arg(long, default_value_t = 2)]
+    pub atlas_padding: usize,
+
+    /// pixels around each icoface filled with what lies across its edges, so textures
+    /// filter cleanly; only the atlas layout has room for them, and its padding widens
+    /// to fit
+    #[arg(long, default_value_t = 0)]
+    pub gutter: usize,
+
+    #[arg(long, default_value_t = WfcConfig::default().max_backtracks)]
+    pub max_backtracks: usize,
+
+    #[arg(long, default_value_t = WfcConfig::default().max_restarts)]
+    pub max_restarts: usize,
+}
+
+impl SolverArgs {
+    pub fn config(&self, seed: u64) -> WfcConfig {
+        return WfcConfig {
+            seed,
+            frequency: self.frequency,
+            layout: self.layout(),
+            max_backtracks: self.max_backtracks,
+            max_restarts: self.max_restarts,
+        };
+    }
+
+    pub fn layout(&self) -> NetLayout {
+        return match self.layout {
+            LayoutArg::Standard => NetLayout::Standard,
+            LayoutArg::TwoStrip => NetLayout::TwoStrip,
+            LayoutArg::Atlas => NetLayout::Atlas {
+                padding: self.atlas_padding.max(self.gutter),
+            },
+            LayoutArg::Dymaxion => NetLayout::Dymaxion,
+        };
+    }
+
+    /// The gutter to bleed, which the other layouts leave no room for
+    pub fn gutter(&self) -> Result<usize, String> {
+        if self.gutter > 0 && self.layout != LayoutArg::Atlas {
+            return Err(format!(
+                "--gutter {} needs space between the icofaces; use it with --layout atlas",
+                self.gutter
+            ));
+        }
+        return Ok(self.gutter);
+    }
+
+    pub fn deltille_options(&self) -> Result<DeltilleOptions, String> {
+        return Tileset::load(&self.tileset)
+            .map(|tileset| tileset.deltille_options())
+            .map_err(|error| format!("could not load {}: {error}", self.tileset.display()));
+    }
+
+    /// The directory deltille image paths are relative to
+    pub fn image_dir(&self) -> &Path {
+        return self.tileset.parent().unwrap_or(Path::new(""));
+    }
+}
+
+/// Solves one tiling and writes its net to `out`, and its mesh and projection if asked
+/// to
+pub fn generate(
+    solver: &SolverArgs,
+    seed: u64,
+    out: &Path,
+    mesh: &MeshOutArgs,
+    projection: &ProjectionArgs,
+) -> Result<(), String> {
+    let gutter = solver.gutter()?;
+    let deltille_options = solver.deltille_options()?;
+    let state = solve(solver, deltille_options, seed)?;
+    export_net(&state, solver.image_dir(), out, gutter).map_err(|error| error.to_string())?;
+    if let Some(mesh_out) = &mesh.mesh_out {
+        export_mesh(&state, solver.image_dir(), mesh_out, mesh.options(gutter))
+            .map_err(|error| error.to_string())?;
+    }
+    if let Some(projection_out) = &projection.projection_out {
+        export_projection(
+            &state,
+            solver.image_dir(),
+            projection_out,
+            projection.projection.into(),
+            projection.projection_size,
+            projection.sampling(),
+        )
+        .map_err(|error| error.to_string())?;
+    }
+    return Ok(());
+}
+
+/// Solves `count` tilings from `first_seed` on, writes `manifest.json` beside them and
+/// returns how many failed; a failed seed is reported and skipped rather than stopping
+/// the batch
+pub fn batch(
+    solver: &SolverArgs,
+    count: u64,
+    first_seed: u64,
+    out_dir: &Path,
+    threads: usize,
+) -> Result<usize, String> {
+    let last_offset = count
+        .checked_sub(1)
+        .ok_or_else(|| "a batch needs at least one seed".to_string())?;
+    let last_seed = first_seed
+        .checked_add(last_offset)
+        .ok_or_else(|| format!("{count} seeds from {first_seed} run past the largest seed"))?;
+    let gutter = solver.gutter()?;
+    let deltille_options = solver.deltille_options()?;
+    fs::create_dir_all(out_dir)
+        .map_err(|error| format!("could not create {}: {error}", out_dir.display()))?;
+    let seeds = first_seed..=last_seed;
+    let report = run_batch(
+        &deltille_options,
+        solver.config(first_seed),
+        seeds,
+        threads,
+        |state| {
+            let out = out_dir.join(format!("{}.png", state.config.seed));
+            export_net(state, solver.image_dir(), &out, gutter)?;
+            return Ok::<_, ExportError>(vec![out]);
+        },
+    )
+    .map_err(|error| format!("could not start the batch: {error}"))?;
+
+    for seed in report.seeds.iter() {
+        match &seed.error {
+            None => println!("seed {}: wrote {}", seed.seed, seed.outputs[0].display()),
+            Some(error) => eprintln!("seed {}: {error}", seed.seed),
+        }
+    }
+    let manifest = out_dir.join("manifest.json");
+    report
+        .write_manifest(&manifest)
+        .map_err(|error| format!("could not write {}: {error}", manifest.display()))?;
+    println!(
+        "{} of {count} seeds solved in {:.1}s",
+        report.succeeded, report.seconds
+    );
+    return Ok(report.failed);
+}
+
+fn solve(
+    solver: &SolverArgs,
+    deltille_options: DeltilleOptions,
+    seed: u64,
+) -> Result<WfcState, String> {
+    return ico_generator::generate(deltille_options, solver.config(seed))
+        .map_err(|error| error.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use bevy::prelude::Vec2;
+    use clap::Parser;
+    use ico_generator::icosahedron::Icosahedron;
+    use ico_generator::mesh::MeshDetail;
+    use ico_generator::mesh_export::TextureMode;
+    use ico_generator::net_layout::NetLayout;
+
+    use super::{batch, generate, Cli, Command, MeshArg};
+
+    #[test]
+    fn parses_generate() {
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "generate",
+            "--tileset",
+            "tiles.json",
+            "--seed",
+            "42",
+            "--out",
+            "planet.png",
+        ]);
+        let Some(Command::Generate {
+            solver, seed, out, ..
+        }) = cli.command
+        else {
+            panic!("expected generate, got {:?}", cli.command);
+        };
+        assert_eq!(solver.tileset.to_str(), Some("tiles.json"));
+        assert_eq!(seed, Some(42));
+        assert_eq!(out.to_str(), Some("planet.png"));
+        assert_eq!(solver.config(42).seed, 42);
+        assert_eq!(solver.config(42).layout, NetLayout::Standard);
+    }
+
+    #[test]
+    fn parses_the_atlas_layout() {
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "view",
+            "--layout",
+            "atlas",
+            "--atlas-padding",
+            "4",
+            "--gutter",
+            "3",
+        ]);
+        let Some(Command::View { solver, .. }) = cli.command else {
+            panic!("expected view, got {:?}", cli.command);
+        };
+        assert_eq!(solver.layout(), NetLayout::Atlas { padding: 4 });
+        assert_eq!(solver.gutter(), Ok(3));
+    }
+
+    #[test]
+    fn gutters_need_the_atlas_layout() {
+        let parse = |layout| {
+            let cli =
+                Cli::parse_from(["ico_generator", "view", "--layout", layout, "--gutter", "6"]);
+            let Some(Command::View { solver, .. }) = cli.command else {
+                panic!("expected view, got {:?}", cli.command);
+            };
+            return solver;
+        };
+        assert!(parse("standard")
+            .gutter()
+            .unwrap_err()
+            .contains("--layout atlas"));
+        assert!(parse("dymaxion").gutter().is_err());
+        let atlas = parse("atlas");
+        assert_eq!(atlas.gutter(), Ok(6));
+        assert_eq!(atlas.layout(), NetLayout::Atlas { padding: 6 });
+    }
+
+    #[test]
+    fn generate_bleeds_the_whole_gutter() {
+        let out = std::env::temp_dir().join(format!("ico_gutter_{}.png", std::process::id()));
+        let gutter = 4;
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "generate",
+            "--seed",
+            "3",
+            "--frequency",
+            "2",
+            "--layout",
+            "atlas",
+            "--atlas-padding",
+            "1",
+            "--gutter",
+            &gutter.to_string(),
+            "--out",
+            out.to_str().unwrap(),
+        ]);
+        let Some(Command::Generate {
+            solver,
+            seed,
+            out,
+            mesh,
+            projection,
+        }) = cli.command
+        else {
+            panic!("expected generate, got {:?}", cli.command);
+        };
+        generate(&solver, seed.unwrap(), &out, &mesh, &projection).unwrap();
+        let net = image::open(&out).unwrap().to_rgba8();
+        fs::remove_file(&out).unwrap();
+
+        let icosahedron = Icosahedron::with_layout(solver.frequency, solver.layout());
+        let net_corners: Vec<[Vec2; 3]> = (0..20)
+            .map(|icoface_id| icosahedron.icoface_net_corners(icoface_id))
+            .collect();
+        let mut bled = 0;
+        for (x, y, pixel) in net.enumerate_pixels() {
+            // the net's y axis points up and the image's points down
+            let point = Vec2::new(x as f32 + 0.5, net.height() as f32 - (y as f32 + 0.5));
+            if net_corners.iter().any(|corners| inside(point, corners)) {
+                continue;
+            }
+            let distance = net_corners
+                .iter()
+                .flat_map(|corners| {
+                    (0..3)
+                        .map(|edge| segment_distance(point, corners[edge], corners[(edge + 1) % 3]))
+                })
+                .fold(f32::MAX, f32::min);
+            if distance < gutter as f32 {
+                assert_ne!(
+                    pixel[3], 0,
+                    "pixel ({x}, {y}) is {distance} from an icoface"
+                );
+                bled += 1;
+            }
+        }
+        assert!(bled > 0);
+
+        // and every icoface has room for all of it, clear of the image's edges and of
+        // the other icofaces' gutters
+        let size = Vec2::new(net.width() as f32, net.height() as f32);
+        for (icoface_id, corners) in net_corners.iter().enumerate() {
+            let centre = corners.iter().sum::<Vec2>() / 3.0;
+            for edge in 0..3 {
+                let middle = (corners[edge] + corners[(edge + 1) % 3]) / 2.0;
+                let out = middle + (middle - centre).normalize() * (gutter as f32 - 0.5);
+                assert!(out.cmpge(Vec2::ZERO).all() && out.cmplt(size).all());
+                for (other_id, other) in net_corners.iter().enumerate() {
+                    if other_id != icoface_id {
+                        let distance = (0..3)
+                            .map(|edge| segment_distance(out, other[edge], other[(edge + 1) % 3]))
+                            .fold(f32::MAX, f32::min);
+                        assert!(distance > gutter as f32);
+                    }
+                }
+            }
+        }
+    }
+
+    fn inside(point: Vec2, corners: &[Vec2; 3]) -> bool {
+        let sides = (0..3)
+            .map(|edge| (corners[(edge + 1) % 3] - corners[edge]).perp_dot(point - corners[edge]));
+        let sides: Vec<f32> = sides.collect();
+        return sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0);
+    }
+
+    fn segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+        let along = end - start;
+        let t = ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0);
+        return point.distance(start + along * t);
+    }
+
+    #[test]
+    fn parses_mesh_export() {
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "generate",
+            "--out",
+            "planet.png",
+            "--mesh-out",
+            "planet.glb",
+            "--mesh-detail",
+            "sphere",
+            "--tile-ids",
+        ]);
+        let Some(Command::Generate { mesh, .. }) = cli.command else {
+            panic!("expected generate, got {:?}", cli.command);
+        };
+        assert_eq!(mesh.mesh_out.as_deref(), Some(Path::new("planet.glb")));
+        let options = mesh.options(0);
+        assert_eq!(options.detail, MeshDetail::Sphere);
+        assert!(options.tile_ids);
+        assert_eq!(options.texture, TextureMode::Embedded);
+    }
+
+    #[test]
+    fn generate_writes_a_cubemap() {
+        let out_dir = std::env::temp_dir().join(format!("ico_cubemap_{}", std::process::id()));
+        let out = out_dir.join("net.png");
+        let sky = out_dir.join("sky.png");
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "generate",
+            "--seed",
+            "1",
+            "--frequency",
+            "2",
+            "--out",
+            out.to_str().unwrap(),
+            "--projection-out",
+            sky.to_str().unwrap(),
+            "--projection",
+            "cubemap",
+            "--projection-size",
+            "8",
+        ]);
+        let Some(Command::Generate {
+            solver,
+            seed,
+            out,
+            mesh,
+            projection,
+        }) = cli.command
+        else {
+            panic!("expected generate, got {:?}", cli.command);
+        };
+        fs::create_dir_all(&out_dir).unwrap();
+        generate(&solver, seed.unwrap(), &out, &mesh, &projection).unwrap();
+        for suffix in ["px", "nx", "py", "ny", "pz", "nz"] {
+            let face = image::open(out_dir.join(format!("sky_{suffix}.png"))).unwrap();
+            assert_eq!((face.width(), face.height()), (8, 8));
+        }
+        fs::remove_dir_all(out_dir).unwrap();
+    }
+
+    #[test]
+    fn view_is_the_default() {
+        assert!(Cli::parse_from(["ico_generator"]).command.is_none());
+    }
+
+    #[test]
+    fn frequencies_start_at_one() {
+        let parse =
+            |frequency| Cli::try_parse_from(["ico_generator", "view", "--frequency", frequency]);
+        assert!(parse("0").is_err());
+        let Some(Command::View { solver, .. }) = parse("1").unwrap().command else {
+            panic!("expected view");
+        };
+        assert_eq!(solver.frequency, 1);
+    }
+
+    #[test]
+    fn parses_the_3d_view() {
+        let cli = Cli::parse_from(["ico_generator", "view", "--3d", "--mesh", "sphere"]);
+        let Some(Command::View {
+            three_d,
+            mesh,
+            playback,
+            ..
+        }) = cli.command
+        else {
+            panic!("expected view, got {:?}", cli.command);
+        };
+        assert!(three_d);
+        assert_eq!(mesh, MeshArg::Sphere);
+        assert!(!playback.paused);
+        assert_eq!(playback.steps_per_frame, 1);
+    }
+
+    #[test]
+    fn batch_writes_one_image_per_seed() {
+        let out_dir = std::env::temp_dir().join(format!("ico_batch_{}", std::process::id()));
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "batch",
+            "--count",
+            "2",
+            "--first-seed",
+            "7",
+            "--out-dir",
+            out_dir.to_str().unwrap(),
+            "--threads",
+            "2",
+        ]);
+        let Some(Command::Batch {
+            solver,
+            count,
+            first_seed,
+            out_dir,
+            threads,
+        }) = cli.command
+        else {
+            panic!("expected batch, got {:?}", cli.command);
+        };
+        assert_eq!(batch(&solver, count, first_seed, &out_dir, threads), Ok(0));
+        assert!(out_dir.join("7.png").is_file());
+        assert!(out_dir.join("8.png").is_file());
+        assert!(out_dir.join("manifest.json").is_file());
+        fs::remove_dir_all(out_dir).unwrap();
+    }
+
+    #[test]
+    fn batches_reach_the_largest_seed() {
+        let out_dir = std::env::temp_dir().join(format!("ico_last_seeds_{}", std::process::id()));
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "batch",
+            "--frequency",
+            "2",
+            "--count",
+            "2",
+            "--out-dir",
+            out_dir.to_str().unwrap(),
+        ]);
+        let Some(Command::Batch { solver, .. }) = cli.command else {
+            panic!("expected batch, got {:?}", cli.command);
+        };
+        assert_eq!(batch(&solver, 2, u64::MAX - 1, &out_dir, 1), Ok(0));
+        assert!(out_dir.join(format!("{}.png", u64::MAX - 1)).is_file());
+        assert!(out_dir.join(format!("{}.png", u64::MAX)).is_file());
+        fs::remove_dir_all(out_dir).unwrap();
+
+        let result = batch(&solver, 3, u64::MAX - 1, Path::new("unused"), 1);
+        assert!(result.unwrap_err().contains("largest seed"));
+        let result = batch(&solver, 0, 0, Path::new("unused"), 1);
+        assert!(result.is_err());
+        let zero =
+            Cli::try_parse_from(["ico_generator", "batch", "--count", "0", "--out-dir", "x"]);
+        assert!(zero.is_err());
+    }
+}
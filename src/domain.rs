@@ -0,0 +1,202 @@
+//! The set of deltille option ids still possible in a slot, one bit per id.
+//!
+//! The solver snapshots every slot's set before each choice and intersects sets while it
+//! propagates, so a set is a fixed array of words: copying it never allocates,
+//! intersecting is one `&` per word and counting is one popcount per word. Ids go up to
+//! `MAX_DELTILLE_OPTIONS`, which tilesets are checked against when they load.
+
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign};
+
+use crate::config_constants::MAX_DELTILLE_OPTIONS;
+
+const WORD_BITS: usize = u64::BITS as usize;
+const WORDS: usize = MAX_DELTILLE_OPTIONS.div_ceil(WORD_BITS);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Domain {
+    words: [u64; WORDS],
+}
+
+impl Domain {
+    /// Ids `0..count`, every option of an orientation
+    pub fn full(count: usize) -> Self {
+        assert!(
+            count <= MAX_DELTILLE_OPTIONS,
+            "{count} options do not fit in a domain of {MAX_DELTILLE_OPTIONS}"
+        );
+        let mut domain = Domain::default();
+        for (word_index, word) in domain.words.iter_mut().enumerate() {
+            let bits = count.saturating_sub(word_index * WORD_BITS).min(WORD_BITS);
+            *word = match bits {
+                WORD_BITS => u64::MAX,
+                _ => (1 << bits) - 1,
+            };
+        }
+        return domain;
+    }
+
+    /// Just `id`, a collapsed slot
+    pub fn single(id: usize) -> Self {
+        let mut domain = Domain::default();
+        domain.insert(id);
+        return domain;
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        return id < MAX_DELTILLE_OPTIONS && self.words[id / WORD_BITS] & bit(id) != 0;
+    }
+
+    pub fn insert(&mut self, id: usize) {
+        assert!(
+            id < MAX_DELTILLE_OPTIONS,
+            "option {id} does not fit in a domain of {MAX_DELTILLE_OPTIONS}"
+        );
+        self.words[id / WORD_BITS] |= bit(id);
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        if id < MAX_DELTILLE_OPTIONS {
+            self.words[id / WORD_BITS] &= !bit(id);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self
+            .words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.words.iter().all(|word| *word == 0);
+    }
+
+    /// The ids in ascending order
+    pub fn iter(&self) -> Iter {
+        return Iter {
+            words: self.words,
+            word_index: 0,
+        };
+    }
+}
+
+fn bit(id: usize) -> u64 {
+    return 1 << (id % WORD_BITS);
+}
+
+impl BitAnd for Domain {
+    type Output = Domain;
+
+    fn bitand(mut self, other: Domain) -> Domain {
+        self &= other;
+        return self;
+    }
+}
+
+impl BitAndAssign for Domain {
+    fn bitand_assign(&mut self, other: Domain) {
+        for (word, other) in self.words.iter_mut().zip(other.words) {
+            *word &= other;
+        }
+    }
+}
+
+impl FromIterator<usize> for Domain {
+    fn from_iter<T: IntoIterator<Item = usize>>(ids: T) -> Self {
+        let mut domain = Domain::default();
+        for id in ids {
+            domain.insert(id);
+        }
+        return domain;
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for Domain {
+    fn from(ids: [usize; N]) -> Self {
+        return ids.into_iter().collect();
+    }
+}
+
+impl IntoIterator for Domain {
+    type Item = usize;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        return self.iter();
+    }
+}
+
+impl IntoIterator for &Domain {
+    type Item = usize;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        return self.iter();
+    }
+}
+
+/// Prints like a set, `{0, 3, 7}`
+impl fmt::Debug for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_set().entries(self.iter()).finish();
+    }
+}
+
+/// Ids of a `Domain` in ascending order
+#[derive(Clone, Debug)]
+pub struct Iter {
+    words: [u64; WORDS],
+    word_index: usize,
+}
+
+impl Iterator for Iter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word_index < WORDS {
+            let word = &mut self.words[self.word_index];
+            if *word != 0 {
+                let offset = word.trailing_zeros() as usize;
+                // clear the lowest set bit
+                *word &= *word - 1;
+                return Some(self.word_index * WORD_BITS + offset);
+            }
+            self.word_index += 1;
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config_constants::MAX_DELTILLE_OPTIONS;
+
+    use super::Domain;
+
+    #[test]
+    fn full_domains_hold_exactly_the_first_ids() {
+        for count in [0, 1, 63, 64, 65, 130, MAX_DELTILLE_OPTIONS] {
+            let domain = Domain::full(count);
+            assert_eq!(domain.len(), count);
+            assert!(domain.iter().eq(0..count), "{count}");
+            assert!(!domain.contains(count));
+        }
+        assert!(Domain::full(0).is_empty());
+    }
+
+    #[test]
+    fn sets_intersect_and_count_across_words() {
+        let mut a = Domain::from([1, 64, 70, 200]);
+        let b = Domain::from([0, 64, 200, 255]);
+        assert_eq!((a & b).iter().collect::<Vec<_>>(), vec![64, 200]);
+        a.remove(64);
+        a.remove(MAX_DELTILLE_OPTIONS + 1);
+        assert_eq!(a.len(), 3);
+        assert!(a.contains(70) && !a.contains(64));
+        a &= Domain::single(70);
+        assert_eq!(a, Domain::single(70));
+        assert_eq!(format!("{a:?}"), "{70}");
+    }
+}
@@ -0,0 +1,537 @@
+//! Renders the chosen deltilles into one image of the unfolded icosahedron net,
+//! without Bevy or a window.
+//!
+//! The image is `Icosahedron::net_grid_width` × `net_grid_height` pixels, one pixel per
+//! grid unit, with the net laid out exactly as the viewer draws it (flipped so the top row
+//! of faces is at the top of the image). Slots that are not collapsed yet stay
+//! transparent.
+//!
+//! Textures sampled with bilinear filtering or mipmaps read past the edges of the
+//! icofaces, so `bleed_gutter` can fill the empty pixels around each icoface with what
+//! lies across the edge on the sphere. Use a layout with room between the icofaces, like
+//! `NetLayout::Atlas`, to give every edge a gutter.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+use bevy::prelude::Vec2;
+use image::{ImageError, Rgba, RgbaImage};
+
+use crate::config_constants::*;
+use crate::icosahedron::{ArrayIndex, Deltille, Icosahedron, VerticalOrientation};
+use crate::wave_function_collapse::WfcState;
+
+#[derive(Debug)]
+pub enum ExportError {
+    LoadTile {
+        path: PathBuf,
+        error: ImageError,
+    },
+    TileSize {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+    Save {
+        path: PathBuf,
+        error: ImageError,
+    },
+    Write {
+        path: PathBuf,
+        error: io::Error,
+    },
+    UnsupportedFormat(PathBuf),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::LoadTile { path, error } => {
+                write!(f, "could not load {}: {error}", path.display())
+            }
+            ExportError::TileSize {
+                path,
+                width,
+                height,
+            } => write!(
+                f,
+                "{} is {width}×{height}, deltilles must be {DELTILLE_GRID_WIDTH}×{DELTILLE_GRID_HEIGHT}",
+                path.display()
+            ),
+            ExportError::Save { path, error } => {
+                write!(f, "could not save {}: {error}", path.display())
+            }
+            ExportError::Write { path, error } => {
+                write!(f, "could not write {}: {error}", path.display())
+            }
+            ExportError::UnsupportedFormat(path) => {
+                write!(f, "{} is not an .obj or .glb file", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// Tile images by `Deltille::image_path`
+pub type TileImages = HashMap<String, RgbaImage>;
+
+/// Loads every tile image the tileset can place, relative to `image_dir`
+pub fn load_tile_images(state: &WfcState, image_dir: &Path) -> Result<TileImages, ExportError> {
+    let mut images = TileImages::new();
+    for deltille in state.deltille_options.deltilles.iter().flatten() {
+        if images.contains_key(&deltille.image_path) {
+            continue;
+        }
+        let path = image_dir.join(&deltille.image_path);
+        let image = image::open(&path)
+            .map_err(|error| ExportError::LoadTile {
+                path: path.clone(),
+                error,
+            })?
+            .into_rgba8();
+        if image.dimensions() != (DELTILLE_GRID_WIDTH as u32, DELTILLE_GRID_HEIGHT as u32) {
+            return Err(ExportError::TileSize {
+                path,
+                width: image.width(),
+                height: image.height(),
+            });
+        }
+        images.insert(deltille.image_path.clone(), image);
+    }
+    return Ok(images);
+}
+
+/// Composites the collapsed deltilles into an image of the net
+pub fn render_net(state: &WfcState, tile_images: &TileImages) -> RgbaImage {
+    let icosahedron = &state.icosahedron;
+    let mut net = RgbaImage::new(
+        icosahedron.net_grid_width() as u32,
+        icosahedron.net_grid_height() as u32,
+    );
+    for slot_id in state.icosahedron.deltille_slot_ids() {
+        let slot = state.icosahedron.get_deltille_slot(&slot_id);
+        let domain = state.domain(&slot_id);
+        if domain.len() != 1 {
+            continue;
+        }
+        let option_id = domain.iter().next().unwrap();
+        let deltille = &state.deltille_options.deltilles[slot.orientation.index()][option_id];
+        // a missing image leaves the slot transparent, like an undecided one
+        if let Some(tile) = tile_images.get(&deltille.image_path) {
+            draw_deltille(&mut net, tile, deltille, slot.position, slot.orientation);
+        }
+    }
+    return net;
+}
+
+/// Fills the transparent pixels up to `gutter` pixels outside each icoface with the
+/// pixels across its nearest edge, from the neighbor `IcoFaceConnection` leads to.
+///
+/// The neighbor is unfolded flat against the edge, as if the net were cut along the
+/// other edges instead, so patterns carry on straight across it. Pixels in the empty
+/// slots of an icoface are not part of the gutter and stay transparent.
+pub fn bleed_gutter(icosahedron: &Icosahedron, net: &mut RgbaImage, gutter: usize) {
+    if gutter == 0 {
+        return;
+    }
+    let icoface_height = icosahedron.icoface_grid_height() as f32;
+    let net_corners: Vec<[Vec2; 3]> = (0..20)
+        .map(|icoface_id| icosahedron.icoface_net_corners(icoface_id))
+        .collect();
+    let source = net.clone();
+    for (x, y, pixel) in net.enumerate_pixels_mut() {
+        if pixel[3] != 0 {
+            continue;
+        }
+        // the net's y axis points up and the image's points down
+        let point = Vec2::new(x as f32 + 0.5, source.height() as f32 - (y as f32 + 0.5));
+        // the icoface the point is closest to, and its weights, one of them negative
+        let mut nearest: Option<(usize, [f32; 3])> = None;
+        let mut nearest_distance = gutter as f32;
+        for (icoface_id, corners) in net_corners.iter().enumerate() {
+            let weights = weights(point, corners);
+            // a weight times the icoface's height is the distance from the opposite edge
+            let distance = -weights.into_iter().reduce(f32::min).unwrap() * icoface_height;
+            if distance <= 0.0 {
+                nearest = None;
+                break;
+            }
+            if distance <= nearest_distance {
+                nearest = Some((icoface_id, weights));
+                nearest_distance = distance;
+            }
+        }
+        let Some((icoface_id, weights)) = nearest else {
+            continue;
+        };
+        let beyond = (0..3)
+            .min_by(|a, b| weights[*a].total_cmp(&weights[*b]))
+            .unwrap();
+        // sockets go round clockwise, so the edge opposite corner `k` is socket `-k`
+        let icoface = &icosahedron.icofaces[icoface_id];
+        let connection = icoface.icoface_connections[(3 - beyond) % 3];
+        let neighbor = &icosahedron.icofaces[connection.target_icoface_id];
+        // unfolding the neighbor puts its far corner at the mirror image of `beyond`, so
+        // `beyond`'s weight moves onto the edge's corners and, negated, onto the far one
+        let margin = 0.5 / icoface_height;
+        let neighbor_weights = neighbor.vertices.map(|vertex| {
+            let weight = match icoface.vertices.iter().position(|other| *other == vertex) {
+                Some(corner) if corner != beyond => weights[corner] + weights[beyond],
+                _ => -weights[beyond],
+            };
+            // keep clear of the neighbor's other edges near the corners
+            return weight.max(margin);
+        });
+        let total: f32 = neighbor_weights.iter().sum();
+        let neighbor_corners = &net_corners[connection.target_icoface_id];
+        let from = (0..3).fold(Vec2::ZERO, |from, corner| {
+            from + neighbor_corners[corner] * neighbor_weights[corner] / total
+        });
+        // where the neighbor's tiles leave a hole, take the first pixel toward its centre
+        let centre = neighbor_corners.iter().sum::<Vec2>() / 3.0;
+        let toward = (centre - from).normalize_or_zero();
+        let steps = from.distance(centre).ceil() as usize;
+        let opaque = (0..=steps)
+            .map(|step| net_pixel(&source, from + toward * step as f32))
+            .find(|pixel| pixel[3] != 0);
+        if let Some(opaque) = opaque {
+            *pixel = opaque;
+        }
+    }
+}
+
+/// The pixel of `net` under a point in grid pixels, clamped to the image
+fn net_pixel(net: &RgbaImage, point: Vec2) -> Rgba<u8> {
+    let x = (point.x.floor().max(0.0) as u32).min(net.width() - 1);
+    let y = ((net.height() as f32 - point.y).floor().max(0.0) as u32).min(net.height() - 1);
+    return *net.get_pixel(x, y);
+}
+
+/// Loads the tile images, renders the net with `gutter` pixels of bleed around each
+/// icoface and writes it to `path`; the format follows the file extension
+pub fn export_net(
+    state: &WfcState,
+    image_dir: &Path,
+    path: &Path,
+    gutter: usize,
+) -> Result<(), ExportError> {
+    let tile_images = load_tile_images(state, image_dir)?;
+    let mut net = render_net(state, &tile_images);
+    bleed_gutter(&state.icosahedron, &mut net, gutter);
+    return net.save(path).map_err(|error| ExportError::Save {
+        path: path.to_path_buf(),
+        error,
+    });
+}
+
+/// Corners of a deltille's bounding box in image pixels:
+///
+/// ```text
+/// Up   T      Down TL-----TR
+///     / \              \    /
+///    /   \              \  /
+///  BL-----BR             B
+/// ```
+///
+/// Up is `[T, BR, BL]`, clockwise from the apex, and Down is the same corners flipped
+/// upside down, `[B, TR, TL]`, so flipping keeps each corner's index.
+fn corners(orientation: VerticalOrientation) -> [Vec2; 3] {
+    let width = DELTILLE_GRID_WIDTH as f32;
+    let height = DELTILLE_GRID_HEIGHT as f32;
+    return match orientation {
+        VerticalOrientation::Up => [
+            Vec2::new(width / 2.0, 0.0),
+            Vec2::new(width, height),
+            Vec2::new(0.0, height),
+        ],
+        VerticalOrientation::Down => [
+            Vec2::new(width / 2.0, height),
+            Vec2::new(width, 0.0),
+            Vec2::new(0.0, 0.0),
+        ],
+    };
+}
+
+/// Draws `tile`, transformed as `deltille` says, into the slot centred on `position`.
+///
+/// Each output pixel is mapped back into the tile by its barycentric coordinates, so
+/// rotations land on the nearest tile pixel and unrotated flips copy pixels exactly.
+fn draw_deltille(
+    net: &mut RgbaImage,
+    tile: &RgbaImage,
+    deltille: &Deltille,
+    position: Vec2,
+    orientation: VerticalOrientation,
+) {
+    // which corner of the slot each corner of the tile ends up in
+    let mut placement = [0, 1, 2];
+    for corner in placement.iter_mut() {
+        *corner = (*corner + deltille.rotation) % 3;
+        if deltille.flip_x {
+            *corner = [0, 2, 1][*corner];
+        }
+    }
+    let tile_corners = corners(VerticalOrientation::Up);
+    for_each_slot_pixel(net, position, orientation, |pixel, weights| {
+        let source = (0..3).fold(Vec2::ZERO, |source, corner| {
+            source + tile_corners[corner] * weights[placement[corner]]
+        });
+        let source_x = (source.x.floor() as u32).min(tile.width() - 1);
+        let source_y = (source.y.floor() as u32).min(tile.height() - 1);
+        *pixel = *tile.get_pixel(source_x, source_y);
+    });
+}
+
+/// Blends `amount` of `color` into the slot centred on `position`, from not at all at 0
+/// to covering it at 1
+pub fn tint_deltille(
+    net: &mut RgbaImage,
+    color: Rgba<u8>,
+    amount: f32,
+    position: Vec2,
+    orientation: VerticalOrientation,
+) {
+    for_each_slot_pixel(net, position, orientation, |pixel, _| {
+        for channel in 0..4 {
+            let blended = pixel[channel] as f32 * (1.0 - amount) + color[channel] as f32 * amount;
+            pixel[channel] = blended.round() as u8;
+        }
+    });
+}
+
+/// Calls `paint` with each pixel of `net` inside the slot centred on `position`, and its
+/// barycentric coordinates in the slot's `corners`
+fn for_each_slot_pixel(
+    net: &mut RgbaImage,
+    position: Vec2,
+    orientation: VerticalOrientation,
+    mut paint: impl FnMut(&mut Rgba<u8>, [f32; 3]),
+) {
+    let slot_corners = corners(orientation);
+    let left = position.x - DELTILLE_GRID_WIDTH as f32 / 2.0;
+    // the net's y axis points up and the image's points down
+    let top = net.height() as f32 - (position.y + DELTILLE_GRID_HEIGHT_HALF);
+    for y in 0..DELTILLE_GRID_HEIGHT {
+        for x in 0..DELTILLE_GRID_WIDTH {
+            let point = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let Some(weights) = barycentric(point, &slot_corners) else {
+                continue;
+            };
+            let net_x = left as i64 + x as i64;
+            let net_y = top as i64 + y as i64;
+            if net_x < 0 || net_y < 0 || net_x >= net.width() as i64 || net_y >= net.height() as i64
+            {
+                continue;
+            }
+            paint(net.get_pixel_mut(net_x as u32, net_y as u32), weights);
+        }
+    }
+}
+
+/// Barycentric coordinates of `point` in the triangle, or `None` outside it
+fn barycentric(point: Vec2, corners: &[Vec2; 3]) -> Option<[f32; 3]> {
+    let weights = weights(point, corners);
+    if weights.iter().any(|weight| *weight < 0.0) {
+        return None;
+    }
+    return Some(weights);
+}
+
+/// Barycentric coordinates of `point` in the triangle's plane, negative outside it
+fn weights(point: Vec2, &[a, b, c]: &[Vec2; 3]) -> [f32; 3] {
+    let area = (b - a).perp_dot(c - a);
+    let weight_a = (b - point).perp_dot(c - point) / area;
+    let weight_b = (c - point).perp_dot(a - point) / area;
+    return [weight_a, weight_b, 1.0 - weight_a - weight_b];
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::prelude::Vec2;
+    use image::{Rgba, RgbaImage};
+
+    use crate::config_constants::*;
+    use crate::icosahedron::{Deltille, Icosahedron, VerticalOrientation};
+    use crate::net_layout::NetLayout;
+    use crate::tileset::Tileset;
+    use crate::wave_function_collapse::{WfcConfig, WfcState};
+
+    use super::{
+        barycentric, bleed_gutter, draw_deltille, load_tile_images, render_net, tint_deltille,
+    };
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// An up tile that is red on its left half and blue on its right half
+    fn half_and_half() -> RgbaImage {
+        return RgbaImage::from_fn(
+            DELTILLE_GRID_WIDTH as u32,
+            DELTILLE_GRID_HEIGHT as u32,
+            |x, _| {
+                if x < DELTILLE_GRID_WIDTH as u32 / 2 {
+                    RED
+                } else {
+                    BLUE
+                }
+            },
+        );
+    }
+
+    fn transformed(rotation: usize, flip_x: bool, flip_y: bool) -> Deltille {
+        return Deltille {
+            image_path: "half.png".to_string(),
+            rotation,
+            flip_x,
+            flip_y,
+            weight: 1.0,
+            sockets: Default::default(),
+        };
+    }
+
+    /// Draws the tile into a canvas the size of one deltille
+    fn draw(deltille: &Deltille, orientation: VerticalOrientation) -> RgbaImage {
+        let mut canvas = RgbaImage::new(DELTILLE_GRID_WIDTH as u32, DELTILLE_GRID_HEIGHT as u32);
+        let position = Vec2::new(DELTILLE_GRID_WIDTH as f32 / 2.0, DELTILLE_GRID_HEIGHT_HALF);
+        draw_deltille(
+            &mut canvas,
+            &half_and_half(),
+            deltille,
+            position,
+            orientation,
+        );
+        return canvas;
+    }
+
+    #[test]
+    fn untransformed_deltilles_are_copied() {
+        let canvas = draw(&transformed(0, false, false), VerticalOrientation::Up);
+        let bottom = DELTILLE_GRID_HEIGHT as u32 - 1;
+        assert_eq!(*canvas.get_pixel(0, bottom), RED);
+        assert_eq!(
+            *canvas.get_pixel(DELTILLE_GRID_WIDTH as u32 - 1, bottom),
+            BLUE
+        );
+        // outside the triangle
+        assert_eq!(canvas.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn mirrored_deltilles_swap_sides() {
+        let canvas = draw(&transformed(0, true, false), VerticalOrientation::Up);
+        let bottom = DELTILLE_GRID_HEIGHT as u32 - 1;
+        assert_eq!(*canvas.get_pixel(0, bottom), BLUE);
+        assert_eq!(
+            *canvas.get_pixel(DELTILLE_GRID_WIDTH as u32 - 1, bottom),
+            RED
+        );
+    }
+
+    #[test]
+    fn flipped_deltilles_point_down() {
+        let canvas = draw(&transformed(0, false, true), VerticalOrientation::Down);
+        assert_eq!(*canvas.get_pixel(0, 0), RED);
+        assert_eq!(*canvas.get_pixel(DELTILLE_GRID_WIDTH as u32 - 1, 0), BLUE);
+        assert_eq!(canvas.get_pixel(0, DELTILLE_GRID_HEIGHT as u32 - 1)[3], 0);
+    }
+
+    #[test]
+    fn rotated_deltilles_turn_clockwise() {
+        // a third of a turn clockwise takes the blue right edge to the bottom edge and
+        // the red left edge to the right edge
+        let canvas = draw(&transformed(1, false, false), VerticalOrientation::Up);
+        let bottom = DELTILLE_GRID_HEIGHT as u32 - 1;
+        assert_eq!(
+            *canvas.get_pixel(DELTILLE_GRID_WIDTH as u32 / 2, bottom),
+            BLUE
+        );
+        assert_eq!(*canvas.get_pixel(11, 6), RED);
+    }
+
+    #[test]
+    fn bundled_tileset_renders_the_whole_net() {
+        let deltille_options = Tileset::load(DELTILLES_PATH).unwrap().deltille_options();
+        let config = WfcConfig {
+            frequency: 3,
+            ..WfcConfig::default()
+        };
+        let mut state = WfcState::new(deltille_options, config);
+        state.run().unwrap();
+        let image_dir = Path::new(DELTILLES_PATH).parent().unwrap();
+        let net = render_net(&state, &load_tile_images(&state, image_dir).unwrap());
+        let icosahedron = &state.icosahedron;
+        assert_eq!(
+            net.dimensions(),
+            (
+                icosahedron.net_grid_width() as u32,
+                icosahedron.net_grid_height() as u32
+            )
+        );
+        // the middle of the first face is covered
+        let face_centre = net.get_pixel(
+            icosahedron.icoface_grid_width() as u32 / 2,
+            icosahedron.icoface_grid_height() as u32 / 2,
+        );
+        assert_eq!(face_centre[3], 255);
+        // the corner left of the first face is not part of the net
+        assert_eq!(net.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn tints_blend_into_the_slot() {
+        let mut net = RgbaImage::from_pixel(
+            DELTILLE_GRID_WIDTH as u32,
+            DELTILLE_GRID_HEIGHT as u32,
+            BLUE,
+        );
+        let centre = Vec2::new(DELTILLE_GRID_WIDTH as f32, DELTILLE_GRID_HEIGHT as f32) / 2.0;
+        tint_deltille(&mut net, RED, 0.5, centre, VerticalOrientation::Up);
+        let (x, y) = (centre.x as u32, centre.y as u32);
+        assert_eq!(*net.get_pixel(x, y), Rgba([128, 0, 128, 255]));
+        // the corners are outside an up deltille
+        assert_eq!(*net.get_pixel(0, 0), BLUE);
+    }
+
+    #[test]
+    fn gutters_continue_the_neighboring_icofaces() {
+        let icosahedron = Icosahedron::with_layout(2, NetLayout::Atlas { padding: 4 });
+        let height = icosahedron.net_grid_height() as f32;
+        let colour = |icoface_id: usize| Rgba([icoface_id as u8, 0, 0, 255]);
+        // every icoface filled with its own colour
+        let mut net = RgbaImage::new(icosahedron.net_grid_width() as u32, height as u32);
+        for (x, y, pixel) in net.enumerate_pixels_mut() {
+            let point = Vec2::new(x as f32 + 0.5, height - (y as f32 + 0.5));
+            for icoface_id in 0..20 {
+                if barycentric(point, &icosahedron.icoface_net_corners(icoface_id)).is_some() {
+                    *pixel = colour(icoface_id);
+                }
+            }
+        }
+        bleed_gutter(&icosahedron, &mut net, 3);
+
+        for icoface_id in 0..20 {
+            let corners = icosahedron.icoface_net_corners(icoface_id);
+            let center = (corners[0] + corners[1] + corners[2]) / 3.0;
+            for (socket, connection) in icosahedron.icofaces[icoface_id]
+                .icoface_connections
+                .iter()
+                .enumerate()
+            {
+                let middle = (corners[(4 - socket) % 3] + corners[(5 - socket) % 3]) / 2.0;
+                let outside = middle + (middle - center).normalize() * 2.0;
+                let pixel = net.get_pixel(outside.x as u32, (height - outside.y) as u32);
+                assert_eq!(
+                    *pixel,
+                    colour(connection.target_icoface_id),
+                    "icoface {icoface_id}, socket {socket}"
+                );
+            }
+        }
+        // the corners of the cells are too far out
+        assert_eq!(net.get_pixel(0, 0)[3], 0);
+    }
+}
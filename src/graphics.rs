@@ -1,36 +1,48 @@
-use bevy::prelude::{Color, Gizmos, Res, Vec2};
+use bevy::prelude::{Color, Gizmos, Res, Resource, Vec2};
 
-use crate::{
+use ico_generator::{
     config_constants::*,
-    icosahedron::{Icosahedron, VerticalOrientation, IcoFace, DeltilleSlot},
+    icosahedron::{DeltilleSlot, IcoFace, Icosahedron, VerticalOrientation},
+    wave_function_collapse::WfcState,
 };
 // use crate::icosahedron::*;
 
-pub fn draw_debug(mut gizmos: Gizmos, icosahedron: Res<Icosahedron>) {
-    draw_pixel_grid(&mut gizmos);
-    debug_icosahedron(&mut gizmos, icosahedron);
+/// What the 2D view draws over the net
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay {
+    /// a line between every pixel of the net
+    pub pixel_grid: bool,
 }
 
-fn draw_pixel_grid(gizmos: &mut Gizmos) {
-    for x in 0..WINDOW_GRID_WIDTH {
+pub fn draw_debug(mut gizmos: Gizmos, state: Res<WfcState>, overlay: Res<DebugOverlay>) {
+    if overlay.pixel_grid {
+        draw_pixel_grid(&mut gizmos, &state.icosahedron);
+    }
+    debug_icosahedron(&mut gizmos, &state.icosahedron);
+}
+
+fn draw_pixel_grid(gizmos: &mut Gizmos, icosahedron: &Icosahedron) {
+    let width = icosahedron.net_grid_width();
+    let height = icosahedron.net_grid_height();
+    for x in 0..width {
         gizmos.line_2d(
             Vec2::new(x as f32, 0.0),
-            Vec2::new(x as f32, WINDOW_GRID_HEIGHT as f32),
+            Vec2::new(x as f32, height as f32),
             Color::DARK_GRAY,
         );
     }
-    for y in 0..WINDOW_GRID_HEIGHT {
+    for y in 0..height {
         gizmos.line_2d(
             Vec2::new(0.0, y as f32),
-            Vec2::new(WINDOW_GRID_WIDTH as f32, y as f32),
+            Vec2::new(width as f32, y as f32),
             Color::DARK_GRAY,
         );
     }
 }
 
-fn debug_icosahedron(gizmos: &mut Gizmos, icosahedron: Res<Icosahedron>) {
-    for icoface in icosahedron.icofaces.iter() {
-        debug_icoface(icoface, gizmos);
+fn debug_icosahedron(gizmos: &mut Gizmos, icosahedron: &Icosahedron) {
+    for (icoface_id, icoface) in icosahedron.icofaces.iter().enumerate() {
+        debug_icoface(icosahedron, icoface_id, icoface, gizmos);
     }
 
     // for icoface_coordinates in precalculated_coordinates.all_ico_face_coordinates.iter() {
@@ -64,15 +76,20 @@ fn debug_icosahedron(gizmos: &mut Gizmos, icosahedron: Res<Icosahedron>) {
     // }
 }
 
-fn debug_icoface(icoface: &IcoFace, gizmos: &mut Gizmos) {
-    for deltille_slot in icoface.deltille_slots.iter() {
+fn debug_icoface(
+    icosahedron: &Icosahedron,
+    icoface_id: usize,
+    icoface: &IcoFace,
+    gizmos: &mut Gizmos,
+) {
+    for deltille_slot in icosahedron.icoface_deltille_slots(icoface_id) {
         debug_deltille_slot(deltille_slot, gizmos);
     }
     draw_triangle(
         &icoface.position,
         &icoface.orientation,
-        ICOFACE_GRID_WIDTH as f32,
-        ICOFACE_GRID_HEIGHT as f32,
+        icosahedron.icoface_grid_width() as f32,
+        icosahedron.icoface_grid_height() as f32,
         Color::GREEN,
         gizmos,
     )
@@ -87,7 +104,6 @@ fn debug_deltille_slot(deltille_slot: &DeltilleSlot, gizmos: &mut Gizmos) {
         Color::DARK_GREEN,
         gizmos,
     )
-    
 }
 
 // fn origin_for_icoface_coordinates(x: usize, y: usize) -> Vec2 {
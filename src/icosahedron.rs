@@ -1,24 +1,22 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::fmt;
 
 use crate::config_constants::*;
-use bevy::prelude::{Vec2, Resource};
+use crate::net_layout::{IcoFacePlacement, NetLayout};
+use bevy::prelude::{Resource, Vec2, Vec3};
 
-// TODO: validate build deltilles function
-// TODO: array[boolean]-backed hash set?
 // TODO: just use hashmaps?
 // TODO: use Rc for tile option references?
-// TODO: flat diltille array and adjacency list?
 
-trait ArrayIndex {
+pub trait ArrayIndex {
     fn index(&self) -> usize;
 }
 
-/// ```
+/// ```text
 /// Up | Down
 /// ∧  |  ∨
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum VerticalOrientation {
     /// ∧
     Up,
@@ -36,20 +34,44 @@ impl ArrayIndex for VerticalOrientation {
     }
 }
 
-const VERTICAL_ORIENTATION_COUNT: usize = 2;
+pub const VERTICAL_ORIENTATION_COUNT: usize = 2;
 
-/// ```
+impl VerticalOrientation {
+    /// The sockets of a deltille with this orientation, in `ArrayIndex` order
+    pub fn sockets(&self) -> [DeltilleFaceSocket; SOCKET_COUNT] {
+        return match self {
+            VerticalOrientation::Up => [
+                DeltilleFaceSocket::NE,
+                DeltilleFaceSocket::S,
+                DeltilleFaceSocket::NW,
+            ],
+            VerticalOrientation::Down => [
+                DeltilleFaceSocket::N,
+                DeltilleFaceSocket::SE,
+                DeltilleFaceSocket::SW,
+            ],
+        };
+    }
+}
+
+/// ```text
 /// NW   NE |    N
 ///    ∧    |    ∨
 ///    S    | SW   SE
 /// ```
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum DeltilleFaceSocket {
+    #[serde(alias = "n")]
     N,
+    #[serde(alias = "ne")]
     NE,
+    #[serde(alias = "se")]
     SE,
+    #[serde(alias = "s")]
     S,
+    #[serde(alias = "sw")]
     SW,
+    #[serde(alias = "nw")]
     NW,
 }
 
@@ -66,153 +88,479 @@ impl ArrayIndex for DeltilleFaceSocket {
     }
 }
 
-const SOCKET_COUNT: usize = 3;
+pub const SOCKET_COUNT: usize = 3;
 
-#[derive(Resource)]
+/// sockets of both orientations together
+pub const DELTILLE_FACE_SOCKET_COUNT: usize = 6;
+
+#[derive(Resource, Clone)]
 pub struct Icosahedron {
-    /// ```
-    /// ∧   ∧   ∧   ∧   ∧
-    /// ∨   ∨   ∨   ∨   ∨
-    ///   ∧   ∧   ∧   ∧   ∧
-    ///   ∨   ∨   ∨   ∨   ∨
-    /// ```
+    /// subdivision frequency: deltilles along each icoface edge, so each icoface holds
+    /// `frequency²` deltille slots
+    pub frequency: usize,
+
+    /// where the icofaces sit in the net
+    pub layout: NetLayout,
+
+    /// by id; the ids name the same faces of the icosahedron in every layout
     pub icofaces: [IcoFace; 20],
+
+    /// every deltille slot, icoface by icoface, by `slot_index`
+    pub deltille_slots: Vec<DeltilleSlot>,
+
+    /// the neighbors across each slot's sockets, by `slot_index`; the same links as
+    /// `DeltilleSlot::connections` in a form that is quicker to follow
+    pub neighbors: Vec<[SlotNeighbor; SOCKET_COUNT]>,
 }
 
 impl Icosahedron {
-    pub fn new(options: &[HashSet<usize>; VERTICAL_ORIENTATION_COUNT]) -> Self {
-        let mut icofaces_in_progress: Vec<IcoFace> = Vec::with_capacity(20);
-        let mut icoface_index = 0;
-
-        // top row
-        let mut position = Vec2 {
-            x: 0.5 * ICOFACE_GRID_WIDTH as f32,
-            y: 2.5 * ICOFACE_GRID_HEIGHT as f32,
+    /// An icosahedron laid out as the standard net
+    pub fn new(frequency: usize) -> Self {
+        return Self::with_layout(frequency, NetLayout::Standard);
+    }
+
+    pub fn with_layout(frequency: usize, layout: NetLayout) -> Self {
+        assert!(frequency > 0, "icofaces need at least one deltille");
+        let placements = layout.placements(
+            Self::icoface_grid_width_for(frequency) as f32,
+            Self::icoface_grid_height_for(frequency) as f32,
+        );
+        let icofaces: [IcoFace; 20] = std::array::from_fn(|icoface_id| {
+            let placement = placements[icoface_id];
+            return IcoFace::new(
+                placement.orientation,
+                placement.position,
+                icoface_connections(&placements, icoface_id),
+                placement.vertices,
+            );
+        });
+        let deltille_slots: Vec<DeltilleSlot> = icofaces
+            .iter()
+            .enumerate()
+            .flat_map(|(icoface_id, icoface)| icoface.deltille_slots(frequency, icoface_id))
+            .collect();
+        let slot_index = |id: &DeltilleSlotId| id.icoface_id * frequency.pow(2) + id.deltille_id;
+        let neighbors = deltille_slots
+            .iter()
+            .map(|slot| {
+                return slot.connections.map(|connection| SlotNeighbor {
+                    index: slot_index(&connection.target_deltille_coordinates) as u32,
+                    socket: connection.target_socket.index() as u8,
+                });
+            })
+            .collect();
+        return Icosahedron {
+            frequency,
+            layout,
+            icofaces,
+            deltille_slots,
+            neighbors,
         };
-        for _ in 0..5 {
-            icofaces_in_progress.push(IcoFace::new(
-                VerticalOrientation::Up,
-                position,
-                options,
-                [
-                    // NE
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::NW,
-                        target_icoface_id: (icoface_index + 1) % 5,
-                    },
-                    // S
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::N,
-                        target_icoface_id: icoface_index + 5,
-                    },
-                    // NW
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::NE,
-                        // +4 is like -1 but avoids a subtract overflow in the usize
-                        target_icoface_id: (icoface_index + 4) % 5,
-                    },
-                ],
-                0,
-            ));
-            icoface_index += 1;
-            position.x += ICOFACE_GRID_WIDTH as f32;
-        }
+    }
 
-        // second row "down" icofaces
-        position.x = 0.5 * ICOFACE_GRID_WIDTH as f32;
-        position.y = 1.5 * ICOFACE_GRID_HEIGHT as f32;
-        for _ in 0..5 {
-            icofaces_in_progress.push(IcoFace::new(
-                VerticalOrientation::Down,
-                position,
-                options,
-                [
-                    // N
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::S,
-                        target_icoface_id: icoface_index - 5,
-                    },
-                    // SE
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::NW,
-                        target_icoface_id: 10 + (icoface_index % 5),
-                    },
-                    // SW
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::NE,
-                        target_icoface_id: 10 + ((icoface_index - 1) % 5),
-                    },
-                ],
-                0,
-            ));
-            icoface_index += 1;
-            position.x += ICOFACE_GRID_WIDTH as f32;
-        }
+    /// Deltille slots in each icoface
+    pub fn icoface_deltille_count(&self) -> usize {
+        return self.frequency.pow(2);
+    }
+
+    /// Deltille slots in the whole icosahedron
+    pub fn deltille_count(&self) -> usize {
+        return 20 * self.icoface_deltille_count();
+    }
+
+    pub fn icoface_grid_width(&self) -> usize {
+        return Self::icoface_grid_width_for(self.frequency);
+    }
+
+    pub fn icoface_grid_height(&self) -> usize {
+        return Self::icoface_grid_height_for(self.frequency);
+    }
+
+    /// Width of the whole net, which depends on the layout: 5½ icofaces for the standard
+    /// net
+    pub fn net_grid_width(&self) -> usize {
+        return self.net_grid_size().0;
+    }
+
+    /// Height of the whole net, which depends on the layout: 3 icofaces for the standard
+    /// net
+    pub fn net_grid_height(&self) -> usize {
+        return self.net_grid_size().1;
+    }
+
+    fn net_grid_size(&self) -> (usize, usize) {
+        return self.layout.net_size(
+            self.icoface_grid_width() as f32,
+            self.icoface_grid_height() as f32,
+        );
+    }
+
+    fn icoface_grid_width_for(frequency: usize) -> usize {
+        return frequency * DELTILLE_GRID_WIDTH;
+    }
+
+    // faces are slightly taller to accomodate imperfect deltille pixel heights
+    fn icoface_grid_height_for(frequency: usize) -> usize {
+        return frequency * DELTILLE_GRID_HEIGHT;
+    }
+
+    /// Corners of an icoface in the net, in the same order as `icoface_corners`
+    pub fn icoface_net_corners(&self, icoface_id: usize) -> [Vec2; 3] {
+        let icoface = &self.icofaces[icoface_id];
+        return triangle_corners(
+            icoface.position,
+            icoface.orientation,
+            self.icoface_grid_width() as f32,
+            self.icoface_grid_height() as f32,
+        );
+    }
+
+    /// Corners of a deltille slot in the net, in the same order as `icoface_corners`
+    pub fn deltille_net_corners(&self, id: &DeltilleSlotId) -> [Vec2; 3] {
+        let slot = self.get_deltille_slot(id);
+        return triangle_corners(
+            slot.position,
+            slot.orientation,
+            DELTILLE_GRID_WIDTH as f32,
+            DELTILLE_GRID_HEIGHT as f32,
+        );
+    }
+
+    /// Corners of an icoface on the unit sphere, counter-clockwise seen from outside, in
+    /// the order of `triangle_corners` for where the layout puts it.
+    ///
+    /// ```text
+    ///  icofaces 0-4    around the north pole
+    ///  icofaces 5-9    one corner on the lower ring, two on the upper
+    ///  icofaces 10-14  two corners on the lower ring, one on the upper
+    ///  icofaces 15-19  around the south pole
+    /// ```
+    pub fn icoface_corners(&self, icoface_id: usize) -> [Vec3; 3] {
+        let vertices = icosahedron_vertices();
+        return self.icofaces[icoface_id]
+            .vertices
+            .map(|vertex| vertices[vertex]);
+    }
+
+    /// The deltille slot a direction from the center points into, and where in it, or
+    /// `None` for a zero direction.
+    ///
+    /// The direction is followed out to the flat icosahedron, so slots split the sphere
+    /// along the great circles through their `sphere_corners`. Finding the icoface takes
+    /// 20 dot products and the slot within it is worked out from there, so the cost does
+    /// not grow with the frequency.
+    pub fn slot_at(&self, direction: Vec3) -> Option<SlotLocation> {
+        let direction = direction.try_normalize()?;
+        // every face is the same distance from the center, so the ray leaves through the
+        // face it is most aligned with
+        let (icoface_id, corners) = (0..self.icofaces.len())
+            .map(|icoface_id| (icoface_id, self.icoface_corners(icoface_id)))
+            .max_by(|(_, a), (_, b)| {
+                let alignment = |[a, b, c]: &[Vec3; 3]| (*a + *b + *c).dot(direction);
+                return alignment(a).total_cmp(&alignment(b));
+            })?;
+        let [a, b, c] = corners;
+        let normal = (b - a).cross(c - a);
+        let point = direction * (a.dot(normal) / direction.dot(normal));
+
+        let deltille_id = Self::deltille_id_at(
+            self.frequency,
+            self.icofaces[icoface_id].orientation,
+            barycentric(point, &corners),
+        );
+        let slot_id = DeltilleSlotId {
+            icoface_id,
+            deltille_id,
+        };
+        return Some(SlotLocation {
+            slot_id,
+            barycentric: barycentric(point, &self.get_deltille_slot(&slot_id).corners),
+        });
+    }
+
+    /// `slot_at` for a latitude and longitude in radians; see `lat_long_direction`
+    pub fn slot_at_lat_long(&self, latitude: f32, longitude: f32) -> SlotLocation {
+        return self
+            .slot_at(lat_long_direction(latitude, longitude))
+            .expect("directions from latitude and longitude have unit length");
+    }
+
+    /// The slot of an icoface holding the point with the given barycentric coordinates,
+    /// in the order of `icoface_corners`.
+    ///
+    /// Scaled by the frequency, each coordinate's whole part counts the rows of slots
+    /// between the point and that corner's opposite edge. The three add up to
+    /// `frequency - 1` in slots pointing the same way as the icoface and to one less in
+    /// the slots turned against it.
+    fn deltille_id_at(
+        frequency: usize,
+        orientation: VerticalOrientation,
+        barycentric: [f32; 3],
+    ) -> usize {
+        let [a, b, c] = barycentric.map(|weight| {
+            return ((weight * frequency as f32).floor().max(0.0) as usize).min(frequency - 1);
+        });
+        let same_way = a + b + c >= frequency - 1;
+        return match orientation {
+            // corners bottom left, bottom right, top; rows grow by two slots downwards,
+            // turned slots first
+            VerticalOrientation::Up => {
+                let row = frequency - 1 - c;
+                let row_start = row.pow(2);
+                if same_way {
+                    row_start + row + b.min(row)
+                } else {
+                    row_start + b.min(row.saturating_sub(1))
+                }
+            }
+            // corners bottom, top right, top left; rows shrink by two slots downwards,
+            // same way slots first
+            VerticalOrientation::Down => {
+                let row = a;
+                let row_size = frequency - row;
+                let row_start = frequency.pow(2) - row_size.pow(2);
+                if same_way || row_size == 1 {
+                    row_start + b.min(row_size - 1)
+                } else {
+                    row_start + row_size + b.min(row_size - 2)
+                }
+            }
+        };
+    }
+
+    /// Where a slot sits in `deltille_slots` and `neighbors`
+    pub fn slot_index(&self, id: &DeltilleSlotId) -> usize {
+        return id.icoface_id * self.icoface_deltille_count() + id.deltille_id;
+    }
 
-        // second row "up" icofaces
-        position.x = ICOFACE_GRID_WIDTH as f32;
-        for _ in 0..5 {
-            icofaces_in_progress.push(IcoFace::new(
-                VerticalOrientation::Up,
-                position,
-                options,
-                [
-                    // NE
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::SW,
-                        target_icoface_id: 5 + ((icoface_index + 1) % 5),
-                    },
-                    // S
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::N,
-                        target_icoface_id: icoface_index + 5,
-                    },
-                    // NW
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::SE,
-                        target_icoface_id: 5 + (icoface_index % 5),
-                    },
-                ],
-                0,
-            ));
-            icoface_index += 1;
-            position.x += ICOFACE_GRID_WIDTH as f32;
+    /// The id of the slot at `index` in `deltille_slots`
+    pub fn slot_id(&self, index: usize) -> DeltilleSlotId {
+        return DeltilleSlotId {
+            icoface_id: index / self.icoface_deltille_count(),
+            deltille_id: index % self.icoface_deltille_count(),
+        };
+    }
+
+    pub fn get_deltille_slot(&self, id: &DeltilleSlotId) -> &DeltilleSlot {
+        return &self.deltille_slots[self.slot_index(id)];
+    }
+
+    pub fn get_deltille_slot_mut(&mut self, id: &DeltilleSlotId) -> &mut DeltilleSlot {
+        let index = self.slot_index(id);
+        return &mut self.deltille_slots[index];
+    }
+
+    /// The slots of one icoface, by deltille id
+    pub fn icoface_deltille_slots(&self, icoface_id: usize) -> &[DeltilleSlot] {
+        let count = self.icoface_deltille_count();
+        return &self.deltille_slots[icoface_id * count..(icoface_id + 1) * count];
+    }
+
+    /// Checks that every deltille connection leads to an existing slot whose connection on
+    /// `target_socket` leads straight back, and returns every link that does not
+    pub fn validate(&self) -> Result<(), Vec<BrokenConnection>> {
+        let mut broken = Vec::new();
+        for slot_id in self.deltille_slot_ids() {
+            let slot = self.get_deltille_slot(&slot_id);
+            for (socket, connection) in slot.orientation.sockets().into_iter().zip(slot.connections)
+            {
+                let broken_connection = |problem| BrokenConnection {
+                    slot_id,
+                    socket,
+                    connection,
+                    problem,
+                };
+                let target_id = connection.target_deltille_coordinates;
+                let in_range = target_id.icoface_id < self.icofaces.len()
+                    && target_id.deltille_id < self.icoface_deltille_count();
+                let Some(target) = in_range.then(|| self.get_deltille_slot(&target_id)) else {
+                    broken.push(broken_connection(ConnectionProblem::MissingTarget));
+                    continue;
+                };
+                if !target
+                    .orientation
+                    .sockets()
+                    .contains(&connection.target_socket)
+                {
+                    broken.push(broken_connection(ConnectionProblem::WrongSocket(
+                        target.orientation,
+                    )));
+                    continue;
+                }
+                let back = target.connections[connection.target_socket.index()];
+                if back.target_deltille_coordinates != slot_id || back.target_socket != socket {
+                    broken.push(broken_connection(ConnectionProblem::NotReciprocal(back)));
+                }
+            }
         }
+        if broken.is_empty() {
+            return Ok(());
+        }
+        return Err(broken);
+    }
+
+    /// Iterates over the ids of every deltille slot, icoface by icoface
+    pub fn deltille_slot_ids(&self) -> impl Iterator<Item = DeltilleSlotId> + '_ {
+        return (0..self.deltille_slots.len()).map(|index| self.slot_id(index));
+    }
+}
+
+/// A deltille connection that `Icosahedron::validate` rejected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrokenConnection {
+    pub slot_id: DeltilleSlotId,
+    pub socket: DeltilleFaceSocket,
+    pub connection: DeltilleConnection,
+    pub problem: ConnectionProblem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionProblem {
+    /// the target slot does not exist
+    MissingTarget,
+
+    /// the target socket is not a socket of the target slot's orientation
+    WrongSocket(VerticalOrientation),
+
+    /// the target slot's connection on the target socket, which leads somewhere else
+    NotReciprocal(DeltilleConnection),
+}
+
+impl fmt::Display for DeltilleSlotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "face {} slot {}", self.icoface_id, self.deltille_id)
+    }
+}
 
-        // bottom row "down" icofaces
-        position.x = ICOFACE_GRID_WIDTH as f32;
-        position.y = 0.5 * ICOFACE_GRID_HEIGHT as f32;
-        for _ in 0..5 {
-            icofaces_in_progress.push(IcoFace::new(
-                VerticalOrientation::Down,
-                position,
-                options,
-                [
-                    // N
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::S,
-                        target_icoface_id: icoface_index - 5,
-                    },
-                    // SE
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::SW,
-                        target_icoface_id: 15 + ((icoface_index + 1) % 5),
-                    },
-                    // SW
-                    IcoFaceConnection {
-                        target_socket: DeltilleFaceSocket::SE,
-                        target_icoface_id: 15 + ((icoface_index - 1) % 5),
-                    },
-                ],
-                0,
-            ));
-            icoface_index += 1;
-            position.x += ICOFACE_GRID_WIDTH as f32;
+impl fmt::Display for BrokenConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target_id = self.connection.target_deltille_coordinates;
+        let target_socket = self.connection.target_socket;
+        write!(
+            f,
+            "{} {:?} -> {target_id} {target_socket:?}: ",
+            self.slot_id, self.socket
+        )?;
+        match self.problem {
+            ConnectionProblem::MissingTarget => write!(f, "no such slot"),
+            ConnectionProblem::WrongSocket(orientation) => {
+                write!(f, "{orientation:?} slots have no {target_socket:?} socket")
+            }
+            ConnectionProblem::NotReciprocal(back) => write!(
+                f,
+                "which leads back to {} {:?}",
+                back.target_deltille_coordinates, back.target_socket
+            ),
         }
+    }
+}
 
-        let icofaces = icofaces_in_progress.try_into().unwrap();
-        return Icosahedron { icofaces };
+/// The 12 vertices of an icosahedron on the unit sphere: the north pole (+y), an upper
+/// ring of 5, a lower ring of 5 turned half a step from it, and the south pole
+pub fn icosahedron_vertices() -> [Vec3; 12] {
+    let ring_height = 1.0 / 5.0_f32.sqrt();
+    let ring_radius = 2.0 * ring_height;
+    let ring_vertex = |turns: f32, height: f32| {
+        let angle = turns * std::f32::consts::TAU / 5.0;
+        return Vec3::new(
+            ring_radius * angle.cos(),
+            height,
+            -ring_radius * angle.sin(),
+        );
+    };
+    let mut vertices = [Vec3::Y; 12];
+    for column in 0..5 {
+        vertices[1 + column] = ring_vertex(column as f32, ring_height);
+        vertices[6 + column] = ring_vertex(column as f32 + 0.5, -ring_height);
     }
+    vertices[11] = Vec3::NEG_Y;
+    return vertices;
+}
+
+/// How the icoface connects to its neighbors across each of its edges, found by the
+/// corners they share
+fn icoface_connections(
+    placements: &[IcoFacePlacement; 20],
+    icoface_id: usize,
+) -> [IcoFaceConnection; SOCKET_COUNT] {
+    let placement = &placements[icoface_id];
+    return std::array::from_fn(|socket| {
+        let [from, to] = edge_vertices(&placement.vertices, socket);
+        for (target_icoface_id, target) in placements.iter().enumerate() {
+            // both faces wind counter-clockwise, so they run along the edge in opposite
+            // directions
+            let target_socket = (0..SOCKET_COUNT).find(|target_socket| {
+                edge_vertices(&target.vertices, *target_socket) == [to, from]
+            });
+            if let Some(target_socket) = target_socket {
+                return IcoFaceConnection {
+                    target_socket: target.orientation.sockets()[target_socket],
+                    target_icoface_id,
+                };
+            }
+        }
+        panic!("icoface {icoface_id} has no neighbor across socket {socket}");
+    });
+}
+
+/// The corners at either end of an edge, counter-clockwise: sockets go round clockwise,
+/// so socket `i` runs from corner `1 - i` to corner `2 - i`
+fn edge_vertices<T: Copy>(corners: &[T; 3], socket: usize) -> [T; 2] {
+    return [corners[(4 - socket) % 3], corners[(5 - socket) % 3]];
+}
+
+/// The unit direction at a latitude and longitude in radians. Latitude runs from -π/2
+/// at the south pole to π/2 at the north pole (+y); longitude 0 is +x and grows
+/// counter-clockwise seen from the north, through the icosahedron's upper ring.
+pub fn lat_long_direction(latitude: f32, longitude: f32) -> Vec3 {
+    return Vec3::new(
+        latitude.cos() * longitude.cos(),
+        latitude.sin(),
+        -latitude.cos() * longitude.sin(),
+    );
+}
+
+/// Barycentric coordinates of a point in the plane of the triangle
+fn barycentric(point: Vec3, &[a, b, c]: &[Vec3; 3]) -> [f32; 3] {
+    let normal = (b - a).cross(c - a);
+    let area = normal.length_squared();
+    let weight_a = (c - b).cross(point - b).dot(normal) / area;
+    let weight_b = (a - c).cross(point - c).dot(normal) / area;
+    return [weight_a, weight_b, 1.0 - weight_a - weight_b];
+}
+
+/// Where a point of the net lands on the flat icoface with the given corners
+fn on_icoface(net_point: Vec2, net_corners: &[Vec2; 3], corners: &[Vec3; 3]) -> Vec3 {
+    let [a, b, c] = *net_corners;
+    let area = (b - a).perp_dot(c - a);
+    let weight_a = (b - net_point).perp_dot(c - net_point) / area;
+    let weight_b = (c - net_point).perp_dot(a - net_point) / area;
+    let weight_c = 1.0 - weight_a - weight_b;
+    return corners[0] * weight_a + corners[1] * weight_b + corners[2] * weight_c;
+}
+
+/// Corners of a triangle in the net, counter-clockwise with y up: bottom left, bottom
+/// right, top for up triangles and bottom, top right, top left for down ones
+pub fn triangle_corners(
+    center: Vec2,
+    orientation: VerticalOrientation,
+    width: f32,
+    height: f32,
+) -> [Vec2; 3] {
+    let half_width = width / 2.0;
+    let half_height = height / 2.0;
+    return match orientation {
+        VerticalOrientation::Up => [
+            Vec2::new(center.x - half_width, center.y - half_height),
+            Vec2::new(center.x + half_width, center.y - half_height),
+            Vec2::new(center.x, center.y + half_height),
+        ],
+        VerticalOrientation::Down => [
+            Vec2::new(center.x, center.y - half_height),
+            Vec2::new(center.x + half_width, center.y + half_height),
+            Vec2::new(center.x - half_width, center.y + half_height),
+        ],
+    };
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -224,12 +572,17 @@ pub struct IcoFaceConnection {
 #[derive(Clone, Debug)]
 pub struct IcoFace {
     pub orientation: VerticalOrientation,
+
+    /// center in the net, in grid pixels
     pub position: Vec2,
-    pub deltille_slots: [DeltilleSlot; ICOFACE_DELTILLE_COUNT],
+
+    /// indices into `icosahedron_vertices` of the corners, in the order of
+    /// `triangle_corners`
+    pub vertices: [usize; 3],
     pub icoface_connections: [IcoFaceConnection; SOCKET_COUNT],
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct DeltilleConnection {
     pub target_socket: DeltilleFaceSocket,
     pub target_deltille_coordinates: DeltilleSlotId,
@@ -239,39 +592,61 @@ impl IcoFace {
     pub fn new(
         vertical_orientation: VerticalOrientation,
         position: Vec2,
-        options: &[HashSet<usize>; VERTICAL_ORIENTATION_COUNT],
         icoface_connections: [IcoFaceConnection; SOCKET_COUNT],
-        this_icoface_index: usize,
+        vertices: [usize; 3],
     ) -> Self {
-        let mut deltille_slots = Self::generate_deltille_slots(
-            position,
-            vertical_orientation,
-            options,
-            icoface_connections,
-            this_icoface_index,
-        );
         return IcoFace {
             orientation: vertical_orientation,
             position,
-            deltille_slots,
+            vertices,
             icoface_connections,
         };
     }
 
+    /// The icoface's `frequency²` deltille slots, by deltille id
+    pub fn deltille_slots(&self, frequency: usize, this_icoface_index: usize) -> Vec<DeltilleSlot> {
+        let mut deltille_slots = Self::generate_deltille_slots(
+            self.position,
+            self.orientation,
+            frequency,
+            self.icoface_connections,
+            this_icoface_index,
+        );
+        let net_corners = triangle_corners(
+            self.position,
+            self.orientation,
+            Icosahedron::icoface_grid_width_for(frequency) as f32,
+            Icosahedron::icoface_grid_height_for(frequency) as f32,
+        );
+        let icosahedron_vertices = icosahedron_vertices();
+        let corners = self.vertices.map(|vertex| icosahedron_vertices[vertex]);
+        for slot in deltille_slots.iter_mut() {
+            slot.corners = triangle_corners(
+                slot.position,
+                slot.orientation,
+                DELTILLE_GRID_WIDTH as f32,
+                DELTILLE_GRID_HEIGHT as f32,
+            )
+            .map(|net_corner| on_icoface(net_corner, &net_corners, &corners));
+        }
+        return deltille_slots;
+    }
+
     fn generate_deltille_slots(
         icoface_position: Vec2,
         vertical_orientation: VerticalOrientation,
-        options: &[HashSet<usize>; VERTICAL_ORIENTATION_COUNT],
+        frequency: usize,
         icoface_connections: [IcoFaceConnection; SOCKET_COUNT],
         this_icoface_index: usize,
-    ) -> [DeltilleSlot; ICOFACE_DELTILLE_COUNT] {
+    ) -> Vec<DeltilleSlot> {
         let mut deltille_slots_in_progress: Vec<DeltilleSlot> =
-            Vec::with_capacity(ICOFACE_DELTILLE_COUNT);
+            Vec::with_capacity(frequency.pow(2));
         let mut row: usize = 0;
         // let mut position_in_row: usize = 0;
         // let mut orientation = vertical_orientation;
-        let mut deltille_position = icoface_position.clone();
-        deltille_position.y += ICOFACE_GRID_HEIGHT_HALF - DELTILLE_GRID_HEIGHT_HALF;
+        let mut deltille_position = icoface_position;
+        deltille_position.y +=
+            (frequency * DELTILLE_GRID_HEIGHT) as f32 / 2.0 - DELTILLE_GRID_HEIGHT_HALF;
         let mut row_size: usize;
         let mut deltille_index = 0;
 
@@ -279,13 +654,12 @@ impl IcoFace {
             // build deltilles for "up" icoface
             VerticalOrientation::Up => {
                 row_size = 0;
-                while row < ICOFACE_DELTILLE_WIDTH {
+                while row < frequency {
                     deltille_position.x = Self::row_reset_position_x(icoface_position.x, row_size);
 
                     // build inside "down" deltille slots
                     for _ in 0..row_size {
                         let orientation = VerticalOrientation::Down;
-                        let deltille_option_ids = options[orientation.index()].clone();
                         let connections: [DeltilleConnection; SOCKET_COUNT] = [
                             // N
                             DeltilleConnection {
@@ -313,10 +687,11 @@ impl IcoFace {
                             },
                         ];
                         deltille_slots_in_progress.push(DeltilleSlot {
-                            deltille_option_ids,
-                            position: deltille_position.clone(),
+                            position: deltille_position,
                             orientation,
                             connections,
+                            // filled in by `IcoFace::deltille_slots`
+                            corners: [Vec3::ZERO; 3],
                         });
                         deltille_position.x += DELTILLE_GRID_WIDTH as f32;
                         deltille_index += 1;
@@ -328,7 +703,6 @@ impl IcoFace {
                     // build outside "up" deltille slots
                     for i in 0..row_size {
                         let orientation = VerticalOrientation::Up;
-                        let deltille_option_ids = options[orientation.index()].clone();
 
                         let connection_ne: DeltilleConnection = if i == row_size - 1 {
                             // if exposed to icoface edge, connect to adjacent icoface
@@ -337,7 +711,12 @@ impl IcoFace {
                             let target_icoface_id = icoface_connections
                                 [DeltilleFaceSocket::NE.index()]
                             .target_icoface_id;
-                            let target_deltille_id = Self::exposed_deltille_id(row, target_socket);
+                            let target_deltille_id = Self::neighbor_deltille_id(
+                                row,
+                                DeltilleFaceSocket::NE,
+                                target_socket,
+                                frequency,
+                            );
                             DeltilleConnection {
                                 target_socket,
                                 target_deltille_coordinates: DeltilleSlotId {
@@ -356,15 +735,19 @@ impl IcoFace {
                             }
                         };
 
-                        let connection_s: DeltilleConnection = if row == ICOFACE_DELTILLE_WIDTH - 1
-                        {
+                        let connection_s: DeltilleConnection = if row == frequency - 1 {
                             // if exposed to icoface edge, connect to adjacent icoface
                             let target_socket =
                                 icoface_connections[DeltilleFaceSocket::S.index()].target_socket;
                             let target_icoface_id = icoface_connections
                                 [DeltilleFaceSocket::S.index()]
                             .target_icoface_id;
-                            let target_deltille_id = Self::exposed_deltille_id(i, target_socket);
+                            let target_deltille_id = Self::neighbor_deltille_id(
+                                i,
+                                DeltilleFaceSocket::S,
+                                target_socket,
+                                frequency,
+                            );
                             DeltilleConnection {
                                 target_socket,
                                 target_deltille_coordinates: DeltilleSlotId {
@@ -390,7 +773,12 @@ impl IcoFace {
                             let target_icoface_id = icoface_connections
                                 [DeltilleFaceSocket::NW.index()]
                             .target_icoface_id;
-                            let target_deltille_id = Self::exposed_deltille_id(row, target_socket);
+                            let target_deltille_id = Self::neighbor_deltille_id(
+                                row,
+                                DeltilleFaceSocket::NW,
+                                target_socket,
+                                frequency,
+                            );
                             DeltilleConnection {
                                 target_socket,
                                 target_deltille_coordinates: DeltilleSlotId {
@@ -410,30 +798,30 @@ impl IcoFace {
                         };
 
                         deltille_slots_in_progress.push(DeltilleSlot {
-                            deltille_option_ids,
-                            position: deltille_position.clone(),
+                            position: deltille_position,
                             orientation,
                             connections: [connection_ne, connection_s, connection_nw],
+                            // filled in by `IcoFace::deltille_slots`
+                            corners: [Vec3::ZERO; 3],
                         });
                         deltille_position.x += DELTILLE_GRID_WIDTH as f32;
                         deltille_index += 1;
                     }
 
-                    deltille_position.y = deltille_position.y - DELTILLE_GRID_HEIGHT as f32;
+                    deltille_position.y -= DELTILLE_GRID_HEIGHT as f32;
                     row += 1;
                 }
             }
 
             // build deltilles for "down" icoface
             VerticalOrientation::Down => {
-                row_size = ICOFACE_DELTILLE_WIDTH;
-                while row < ICOFACE_DELTILLE_WIDTH {
+                row_size = frequency;
+                while row < frequency {
                     deltille_position.x = Self::row_reset_position_x(icoface_position.x, row_size);
 
                     // build outside "down" deltille slots
                     for i in 0..row_size {
                         let orientation = VerticalOrientation::Down;
-                        let deltille_option_ids = options[orientation.index()].clone();
 
                         let connection_n: DeltilleConnection = if row == 0 {
                             // if exposed to icoface edge, connect to adjacent icoface
@@ -442,7 +830,12 @@ impl IcoFace {
                             let target_icoface_id = icoface_connections
                                 [DeltilleFaceSocket::N.index()]
                             .target_icoface_id;
-                            let target_deltille_id = Self::exposed_deltille_id(i, target_socket);
+                            let target_deltille_id = Self::neighbor_deltille_id(
+                                i,
+                                DeltilleFaceSocket::N,
+                                target_socket,
+                                frequency,
+                            );
                             DeltilleConnection {
                                 target_socket,
                                 target_deltille_coordinates: DeltilleSlotId {
@@ -461,14 +854,19 @@ impl IcoFace {
                             }
                         };
 
-                        let connection_se: DeltilleConnection = if i == row_size - i {
+                        let connection_se: DeltilleConnection = if i == row_size - 1 {
                             // if exposed to icoface edge, connect to adjacent icoface
                             let target_socket =
                                 icoface_connections[DeltilleFaceSocket::SE.index()].target_socket;
                             let target_icoface_id = icoface_connections
                                 [DeltilleFaceSocket::SE.index()]
                             .target_icoface_id;
-                            let target_deltille_id = Self::exposed_deltille_id(row, target_socket);
+                            let target_deltille_id = Self::neighbor_deltille_id(
+                                row,
+                                DeltilleFaceSocket::SE,
+                                target_socket,
+                                frequency,
+                            );
                             DeltilleConnection {
                                 target_socket,
                                 target_deltille_coordinates: DeltilleSlotId {
@@ -494,7 +892,12 @@ impl IcoFace {
                             let target_icoface_id = icoface_connections
                                 [DeltilleFaceSocket::SW.index()]
                             .target_icoface_id;
-                            let target_deltille_id = Self::exposed_deltille_id(row, target_socket);
+                            let target_deltille_id = Self::neighbor_deltille_id(
+                                row,
+                                DeltilleFaceSocket::SW,
+                                target_socket,
+                                frequency,
+                            );
                             DeltilleConnection {
                                 target_socket,
                                 target_deltille_coordinates: DeltilleSlotId {
@@ -514,10 +917,11 @@ impl IcoFace {
                         };
 
                         deltille_slots_in_progress.push(DeltilleSlot {
-                            deltille_option_ids,
-                            position: deltille_position.clone(),
+                            position: deltille_position,
                             orientation,
                             connections: [connection_n, connection_se, connection_sw],
+                            // filled in by `IcoFace::deltille_slots`
+                            corners: [Vec3::ZERO; 3],
                         });
                         deltille_position.x += DELTILLE_GRID_WIDTH as f32;
                         deltille_index += 1;
@@ -529,7 +933,6 @@ impl IcoFace {
                     // build inside "up" deltille slots
                     for _ in 0..row_size {
                         let orientation = VerticalOrientation::Up;
-                        let deltille_option_ids = options[orientation.index()].clone();
                         let connections: [DeltilleConnection; SOCKET_COUNT] = [
                             // NE
                             DeltilleConnection {
@@ -552,98 +955,234 @@ impl IcoFace {
                                 target_socket: DeltilleFaceSocket::SE,
                                 target_deltille_coordinates: DeltilleSlotId {
                                     icoface_id: this_icoface_index,
-                                    deltille_id: deltille_index + row_size,
+                                    deltille_id: deltille_index - row_size - 1,
                                 },
                             },
                         ];
                         deltille_slots_in_progress.push(DeltilleSlot {
-                            deltille_option_ids,
-                            position: deltille_position.clone(),
+                            position: deltille_position,
                             orientation,
                             connections,
+                            // filled in by `IcoFace::deltille_slots`
+                            corners: [Vec3::ZERO; 3],
                         });
                         deltille_position.x += DELTILLE_GRID_WIDTH as f32;
                         deltille_index += 1;
                     }
 
-                    deltille_position.y = deltille_position.y - DELTILLE_GRID_HEIGHT as f32;
+                    deltille_position.y -= DELTILLE_GRID_HEIGHT as f32;
                     row += 1;
                 }
             }
         };
-        return deltille_slots_in_progress.try_into().unwrap();
+        return deltille_slots_in_progress;
     }
 
     fn row_reset_position_x(icoface_origin_x: f32, row_size: usize) -> f32 {
         icoface_origin_x - ((row_size as f32 - 1.) / 2. * DELTILLE_GRID_WIDTH as f32)
     }
 
+    /// The deltille across an icoface edge from the exposed deltille at `position` along
+    /// this icoface's `socket` edge, on the neighbor's `target_socket` edge.
+    ///
+    /// Both faces count positions top to bottom and left to right in their own
+    /// orientation. N, NE and SE edges count from the end a counter-clockwise walk
+    /// around the face reaches last, and S, SW and NW edges from the end it reaches
+    /// first. The neighbor walks the shared edge the other way, so the counts agree when
+    /// the two edges are of different kinds and run opposite ways when they are alike,
+    /// which only happens where a layout turns a face.
+    fn neighbor_deltille_id(
+        position: usize,
+        socket: DeltilleFaceSocket,
+        target_socket: DeltilleFaceSocket,
+        frequency: usize,
+    ) -> usize {
+        let counts_from_end = |socket: DeltilleFaceSocket| {
+            return matches!(
+                socket,
+                DeltilleFaceSocket::N | DeltilleFaceSocket::NE | DeltilleFaceSocket::SE
+            );
+        };
+        let mut target_position = position;
+        if counts_from_end(socket) == counts_from_end(target_socket) {
+            target_position = frequency - 1 - position;
+        }
+        return Self::exposed_deltille_id(target_position, target_socket, frequency);
+    }
+
     /// Returns an index for an exposed deltille within an icoface given
     ///
     /// # Arguments
     ///
     /// * `position` - the 0-based index of the detille with respect to its icoface edge,
-    /// top to bottom and left to right
+    ///   top to bottom and left to right
     ///
     /// * `orientation` - the orientation of the icoface edge
-    fn exposed_deltille_id(position: usize, orientation: DeltilleFaceSocket) -> usize {
+    ///
+    /// * `frequency` - deltilles along each icoface edge
+    fn exposed_deltille_id(
+        position: usize,
+        orientation: DeltilleFaceSocket,
+        frequency: usize,
+    ) -> usize {
+        let count = frequency.pow(2);
         return match orientation {
             DeltilleFaceSocket::N => position,
             DeltilleFaceSocket::NE => (position + 1).pow(2) - 1,
             DeltilleFaceSocket::SE => {
-                ICOFACE_DELTILLE_COUNT
-                    - 1
-                    - (ICOFACE_DELTILLE_WIDTH - position - 1) * (ICOFACE_DELTILLE_WIDTH - position)
-            }
-            DeltilleFaceSocket::S => ICOFACE_DELTILLE_COUNT - (ICOFACE_DELTILLE_WIDTH - position),
-            DeltilleFaceSocket::SW => {
-                ICOFACE_DELTILLE_COUNT - (ICOFACE_DELTILLE_WIDTH - position).pow(2)
+                count - 1 - (frequency - position - 1) * (frequency - position)
             }
+            DeltilleFaceSocket::S => count - (frequency - position),
+            DeltilleFaceSocket::SW => count - (frequency - position).pow(2),
             DeltilleFaceSocket::NW => position * (position + 1),
         };
     }
 }
 
-fn default_flip() -> bool {
-    false
-}
-
-#[derive(Clone, Serialize, Deserialize)]
+/// A deltille as placed in a slot of one orientation; see `tileset` for how these are
+/// loaded from their JSON definitions
+#[derive(Clone, Debug)]
 pub struct Deltille {
     pub image_path: String,
-    #[serde(default = "default_flip")]
+
+    /// the image is drawn rotated this many thirds of a turn clockwise, then mirrored
+    /// left to right if `flip_x`, then flipped upside down if `flip_y`
+    pub rotation: usize,
     pub flip_x: bool,
-    #[serde(default = "default_flip")]
     pub flip_y: bool,
+
+    /// relative likelihood of being chosen when a slot collapses
+    pub weight: f32,
     pub sockets: [String; SOCKET_COUNT],
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A point inside a deltille slot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlotLocation {
+    pub slot_id: DeltilleSlotId,
+
+    /// weights of the slot's `corners` that give the point on the flat icoface; all
+    /// between 0 and 1 up to rounding
+    pub barycentric: [f32; 3],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct DeltilleSlotId {
     pub icoface_id: usize,
     pub deltille_id: usize,
 }
 
+/// A slot's neighbor across one of its sockets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotNeighbor {
+    /// into `Icosahedron::deltille_slots`
+    pub index: u32,
+
+    /// the socket on the neighbor's side, as an index into its orientation's `sockets()`
+    pub socket: u8,
+}
+
 #[derive(Clone, Debug)]
 pub struct DeltilleSlot {
-    pub deltille_option_ids: HashSet<usize>,
+    /// center in the net, in grid pixels
     pub position: Vec2,
     pub orientation: VerticalOrientation,
     pub connections: [DeltilleConnection; SOCKET_COUNT],
+
+    /// corners on the flat icoface, in the same order as `Icosahedron::deltille_net_corners`
+    pub corners: [Vec3; 3],
+}
+
+impl DeltilleSlot {
+    /// Center on the flat icoface
+    pub fn center(&self) -> Vec3 {
+        return (self.corners[0] + self.corners[1] + self.corners[2]) / 3.0;
+    }
+
+    /// Corners pushed out onto the unit sphere
+    pub fn sphere_corners(&self) -> [Vec3; 3] {
+        return self.corners.map(Vec3::normalize);
+    }
+
+    /// Which way the slot faces from the center of the sphere: its center pushed out onto
+    /// the unit sphere
+    pub fn direction(&self) -> Vec3 {
+        return self.center().normalize();
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{array::from_fn, collections::HashSet};
+    use std::collections::{HashMap, HashSet, VecDeque};
+
+    use std::f32::consts::FRAC_PI_2;
 
-    use bevy::prelude::Vec2;
+    use bevy::prelude::{Vec2, Vec3};
 
-    use crate::{
-        config_constants::{ICOFACE_DELTILLE_COUNT, ICOFACE_DELTILLE_WIDTH},
-        icosahedron::{
-            self, DeltilleFaceSocket, IcoFace, IcoFaceConnection, Icosahedron, VerticalOrientation,
-        },
+    use crate::icosahedron::{
+        lat_long_direction, ArrayIndex, ConnectionProblem, DeltilleFaceSocket, DeltilleSlotId,
+        IcoFace, IcoFaceConnection, Icosahedron, VerticalOrientation,
     };
+    use crate::net_layout::NetLayout;
+
+    const FREQUENCY: usize = 4;
+
+    /// subdivision frequencies the topology tests run for
+    const FREQUENCIES: [usize; 6] = [1, 2, 3, 4, 5, 8];
+
+    const LAYOUTS: [NetLayout; 4] = [
+        NetLayout::Standard,
+        NetLayout::TwoStrip,
+        NetLayout::Atlas { padding: 3 },
+        NetLayout::Dymaxion,
+    ];
+
+    /// Groups deltille corners into the vertices of the net, returning how many deltilles
+    /// meet at each vertex.
+    ///
+    /// Read clockwise, edge `i` of every deltille runs from corner `i` to corner `i + 1`
+    /// (NE, S, NW for up deltilles and N, SE, SW for down ones). The neighbor across it
+    /// reads the same edge the other way, which ties its corners to ours.
+    fn vertex_degrees(icosahedron: &Icosahedron) -> Vec<usize> {
+        let slot_ids: Vec<DeltilleSlotId> = icosahedron.deltille_slot_ids().collect();
+        let slot_index: HashMap<DeltilleSlotId, usize> = slot_ids
+            .iter()
+            .enumerate()
+            .map(|(index, slot_id)| (*slot_id, index))
+            .collect();
+
+        // union find over every (slot, corner)
+        let mut parents: Vec<usize> = (0..slot_ids.len() * 3).collect();
+        fn root(parents: &mut [usize], mut corner: usize) -> usize {
+            while parents[corner] != corner {
+                parents[corner] = parents[parents[corner]];
+                corner = parents[corner];
+            }
+            return corner;
+        }
+        for (index, slot_id) in slot_ids.iter().enumerate() {
+            let slot = icosahedron.get_deltille_slot(slot_id);
+            for (edge, connection) in slot.connections.iter().enumerate() {
+                let neighbor = slot_index[&connection.target_deltille_coordinates];
+                let neighbor_edge = connection.target_socket.index();
+                let pairs = [
+                    (index * 3 + edge, neighbor * 3 + (neighbor_edge + 1) % 3),
+                    (index * 3 + (edge + 1) % 3, neighbor * 3 + neighbor_edge),
+                ];
+                for (corner, neighbor_corner) in pairs {
+                    let corner_root = root(&mut parents, corner);
+                    let neighbor_root = root(&mut parents, neighbor_corner);
+                    parents[corner_root] = neighbor_root;
+                }
+            }
+        }
+
+        let mut degrees: HashMap<usize, usize> = HashMap::new();
+        for corner in 0..parents.len() {
+            *degrees.entry(root(&mut parents, corner)).or_default() += 1;
+        }
+        return degrees.into_values().collect();
+    }
 
     #[test]
     fn generate_deltille_slots_works() {
@@ -651,7 +1190,7 @@ mod tests {
         let result_up = IcoFace::generate_deltille_slots(
             position,
             VerticalOrientation::Up,
-            &from_fn(|_| HashSet::new()),
+            FREQUENCY,
             [
                 IcoFaceConnection {
                     target_socket: DeltilleFaceSocket::N,
@@ -668,12 +1207,12 @@ mod tests {
             ],
             0,
         );
-        assert_eq!(result_up.len(), ICOFACE_DELTILLE_COUNT);
+        assert_eq!(result_up.len(), FREQUENCY.pow(2));
 
         let result_down = IcoFace::generate_deltille_slots(
             position,
             VerticalOrientation::Down,
-            &from_fn(|_| HashSet::new()),
+            FREQUENCY,
             [
                 IcoFaceConnection {
                     target_socket: DeltilleFaceSocket::N,
@@ -690,97 +1229,416 @@ mod tests {
             ],
             0,
         );
-        assert_eq!(result_down.len(), ICOFACE_DELTILLE_COUNT);
+        assert_eq!(result_down.len(), FREQUENCY.pow(2));
     }
 
     #[test]
     fn exposed_deltille_index_n() {
-        assert_eq!(IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::N), 0);
-        if ICOFACE_DELTILLE_WIDTH >= 2 {
-            assert_eq!(IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::N), 1);
-        }
-        if ICOFACE_DELTILLE_WIDTH >= 3 {
-            assert_eq!(IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::N), 2);
-        }
-        if ICOFACE_DELTILLE_WIDTH >= 4 {
-            assert_eq!(IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::N), 3);
-        }
+        assert_eq!(
+            IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::N, FREQUENCY),
+            0
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::N, FREQUENCY),
+            1
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::N, FREQUENCY),
+            2
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::N, FREQUENCY),
+            3
+        );
     }
 
     #[test]
     fn exposed_deltille_index_ne() {
-        assert_eq!(IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::NE), 0);
-        if ICOFACE_DELTILLE_WIDTH >= 2 {
-            assert_eq!(IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::NE), 3);
-        }
-        if ICOFACE_DELTILLE_WIDTH >= 3 {
-            assert_eq!(IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::NE), 8);
-        }
-        if ICOFACE_DELTILLE_WIDTH >= 4 {
-            assert_eq!(IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::NE), 15);
-        }
+        assert_eq!(
+            IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::NE, FREQUENCY),
+            0
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::NE, FREQUENCY),
+            3
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::NE, FREQUENCY),
+            8
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::NE, FREQUENCY),
+            15
+        );
     }
 
     #[test]
     fn exposed_deltille_index_se() {
-        assert_eq!(IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::SE), 3);
-        if ICOFACE_DELTILLE_WIDTH >= 2 {
-            assert_eq!(IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::SE), 9);
-        }
-        if ICOFACE_DELTILLE_WIDTH >= 3 {
-            assert_eq!(IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::SE), 13);
-        }
-        if ICOFACE_DELTILLE_WIDTH >= 4 {
-            assert_eq!(IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::SE), 15);
-        }
+        assert_eq!(
+            IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::SE, FREQUENCY),
+            3
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::SE, FREQUENCY),
+            9
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::SE, FREQUENCY),
+            13
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::SE, FREQUENCY),
+            15
+        );
     }
 
     #[test]
     fn exposed_deltille_index_s() {
-        assert_eq!(IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::S), 12);
-        if ICOFACE_DELTILLE_WIDTH >= 2 {
-            assert_eq!(IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::S), 13);
+        assert_eq!(
+            IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::S, FREQUENCY),
+            12
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::S, FREQUENCY),
+            13
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::S, FREQUENCY),
+            14
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::S, FREQUENCY),
+            15
+        );
+    }
+
+    #[test]
+    fn exposed_deltille_index_sw() {
+        assert_eq!(
+            IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::SW, FREQUENCY),
+            0
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::SW, FREQUENCY),
+            7
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::SW, FREQUENCY),
+            12
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::SW, FREQUENCY),
+            15
+        );
+    }
+
+    #[test]
+    fn exposed_deltille_index_nw() {
+        assert_eq!(
+            IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::NW, FREQUENCY),
+            0
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::NW, FREQUENCY),
+            2
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::NW, FREQUENCY),
+            6
+        );
+        assert_eq!(
+            IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::NW, FREQUENCY),
+            12
+        );
+    }
+
+    #[test]
+    fn new_icosahedron_works() {
+        let icosahedron = Icosahedron::new(FREQUENCY);
+        assert_eq!(icosahedron.icofaces.len(), 20);
+    }
+
+    #[test]
+    fn frequency_sets_the_slot_count() {
+        for frequency in [1, 2, 5, 16] {
+            let icosahedron = Icosahedron::new(frequency);
+            assert_eq!(
+                icosahedron.deltille_slot_ids().count(),
+                20 * frequency.pow(2)
+            );
+            assert_eq!(icosahedron.deltille_count(), 20 * frequency.pow(2));
         }
-        if ICOFACE_DELTILLE_WIDTH >= 3 {
-            assert_eq!(IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::S), 14);
+    }
+
+    #[test]
+    fn connections_are_reciprocal() {
+        for frequency in FREQUENCIES {
+            for layout in LAYOUTS {
+                let icosahedron = Icosahedron::with_layout(frequency, layout);
+                if let Err(broken) = icosahedron.validate() {
+                    let report: Vec<String> = broken.iter().map(|link| link.to_string()).collect();
+                    panic!("frequency {frequency}, {layout:?}:\n{}", report.join("\n"));
+                }
+            }
         }
-        if ICOFACE_DELTILLE_WIDTH >= 4 {
-            assert_eq!(IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::S), 15);
+    }
+
+    #[test]
+    fn validate_reports_broken_connections() {
+        let mut icosahedron = Icosahedron::new(FREQUENCY);
+        let slot_id = DeltilleSlotId {
+            icoface_id: 3,
+            deltille_id: 5,
+        };
+        let connection = &mut icosahedron.get_deltille_slot_mut(&slot_id).connections[0];
+        let original_target = connection.target_deltille_coordinates;
+        connection.target_deltille_coordinates.deltille_id = 0;
+
+        let broken = icosahedron.validate().unwrap_err();
+        // the link itself, and the old target that still leads to it
+        let broken_slots: Vec<DeltilleSlotId> = broken.iter().map(|link| link.slot_id).collect();
+        assert_eq!(broken_slots, [original_target, slot_id]);
+        assert!(matches!(
+            broken[1].problem,
+            ConnectionProblem::NotReciprocal(_)
+        ));
+        assert!(broken[1]
+            .to_string()
+            .starts_with("face 3 slot 5 N -> face 3 slot 0 S"));
+
+        icosahedron.get_deltille_slot_mut(&slot_id).connections[0]
+            .target_deltille_coordinates
+            .deltille_id = 99;
+        let broken = icosahedron.validate().unwrap_err();
+        assert_eq!(broken[1].problem, ConnectionProblem::MissingTarget);
+    }
+
+    #[test]
+    fn every_icoface_has_frequency_squared_slots() {
+        for frequency in FREQUENCIES {
+            let icosahedron = Icosahedron::new(frequency);
+            assert_eq!(
+                icosahedron.deltille_slot_ids().count(),
+                20 * frequency.pow(2)
+            );
+            for icoface_id in 0..20 {
+                assert_eq!(
+                    icosahedron.icoface_deltille_slots(icoface_id).len(),
+                    frequency.pow(2)
+                );
+            }
         }
     }
 
     #[test]
-    fn exposed_deltille_index_sw() {
-        assert_eq!(IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::SW), 0);
-        if ICOFACE_DELTILLE_WIDTH >= 2 {
-            assert_eq!(IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::SW), 7);
+    fn neighbor_table_follows_the_connections() {
+        for frequency in FREQUENCIES {
+            let icosahedron = Icosahedron::new(frequency);
+            for (index, slot) in icosahedron.deltille_slots.iter().enumerate() {
+                assert_eq!(icosahedron.slot_index(&icosahedron.slot_id(index)), index);
+                for (neighbor, connection) in
+                    icosahedron.neighbors[index].iter().zip(slot.connections)
+                {
+                    assert_eq!(
+                        icosahedron.slot_id(neighbor.index as usize),
+                        connection.target_deltille_coordinates
+                    );
+                    assert_eq!(neighbor.socket as usize, connection.target_socket.index());
+                }
+            }
         }
-        if ICOFACE_DELTILLE_WIDTH >= 3 {
-            assert_eq!(IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::SW), 12);
+    }
+
+    #[test]
+    fn every_slot_has_three_distinct_neighbors() {
+        for frequency in FREQUENCIES {
+            let icosahedron = Icosahedron::new(frequency);
+            for slot_id in icosahedron.deltille_slot_ids() {
+                let neighbors: HashSet<DeltilleSlotId> = icosahedron
+                    .get_deltille_slot(&slot_id)
+                    .connections
+                    .iter()
+                    .map(|connection| connection.target_deltille_coordinates)
+                    .collect();
+                assert_eq!(neighbors.len(), 3, "frequency {frequency}, {slot_id}");
+                assert!(
+                    !neighbors.contains(&slot_id),
+                    "frequency {frequency}, {slot_id}"
+                );
+            }
         }
-        if ICOFACE_DELTILLE_WIDTH >= 4 {
-            assert_eq!(IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::SW), 15);
+    }
+
+    #[test]
+    fn twelve_vertices_have_five_deltilles_and_the_rest_six() {
+        for frequency in FREQUENCIES {
+            let degrees = vertex_degrees(&Icosahedron::new(frequency));
+            let fives = degrees.iter().filter(|degree| **degree == 5).count();
+            let sixes = degrees.iter().filter(|degree| **degree == 6).count();
+            assert_eq!(fives, 12, "frequency {frequency}");
+            assert_eq!(sixes, degrees.len() - 12, "frequency {frequency}");
         }
     }
 
     #[test]
-    fn exposed_deltille_index_nw() {
-        assert_eq!(IcoFace::exposed_deltille_id(0, DeltilleFaceSocket::NW), 0);
-        if ICOFACE_DELTILLE_WIDTH >= 2 {
-            assert_eq!(IcoFace::exposed_deltille_id(1, DeltilleFaceSocket::NW), 2);
+    fn euler_characteristic_is_two() {
+        for frequency in FREQUENCIES {
+            let icosahedron = Icosahedron::new(frequency);
+            let faces = icosahedron.deltille_slot_ids().count() as i64;
+            let edges: HashSet<(DeltilleSlotId, DeltilleSlotId)> = icosahedron
+                .deltille_slot_ids()
+                .flat_map(|slot_id| {
+                    let icosahedron = &icosahedron;
+                    icosahedron
+                        .get_deltille_slot(&slot_id)
+                        .connections
+                        .iter()
+                        .map(move |connection| {
+                            let neighbor_id = connection.target_deltille_coordinates;
+                            let key = |id: DeltilleSlotId| (id.icoface_id, id.deltille_id);
+                            if key(slot_id) < key(neighbor_id) {
+                                (slot_id, neighbor_id)
+                            } else {
+                                (neighbor_id, slot_id)
+                            }
+                        })
+                })
+                .collect();
+            let vertices = vertex_degrees(&icosahedron).len() as i64;
+            assert_eq!(
+                vertices - edges.len() as i64 + faces,
+                2,
+                "frequency {frequency}"
+            );
         }
-        if ICOFACE_DELTILLE_WIDTH >= 3 {
-            assert_eq!(IcoFace::exposed_deltille_id(2, DeltilleFaceSocket::NW), 6);
+    }
+
+    #[test]
+    fn all_slots_are_connected() {
+        for frequency in FREQUENCIES {
+            let icosahedron = Icosahedron::new(frequency);
+            let start = icosahedron.deltille_slot_ids().next().unwrap();
+            let mut seen = HashSet::from([start]);
+            let mut queue = VecDeque::from([start]);
+            while let Some(slot_id) = queue.pop_front() {
+                for connection in icosahedron.get_deltille_slot(&slot_id).connections {
+                    if seen.insert(connection.target_deltille_coordinates) {
+                        queue.push_back(connection.target_deltille_coordinates);
+                    }
+                }
+            }
+            assert_eq!(
+                seen.len(),
+                icosahedron.deltille_slot_ids().count(),
+                "frequency {frequency}"
+            );
+        }
+    }
+
+    #[test]
+    fn icoface_corners_wind_outward_and_meet_their_neighbors() {
+        let icosahedron = Icosahedron::new(FREQUENCY);
+        for (icoface_id, icoface) in icosahedron.icofaces.iter().enumerate() {
+            let [a, b, c] = icosahedron.icoface_corners(icoface_id);
+            assert!(
+                (b - a).cross(c - a).dot(a + b + c) > 0.0,
+                "icoface {icoface_id}"
+            );
+            for connection in icoface.icoface_connections {
+                let neighbor_corners = icosahedron.icoface_corners(connection.target_icoface_id);
+                let shared = [a, b, c]
+                    .iter()
+                    .filter(|corner| {
+                        neighbor_corners
+                            .iter()
+                            .any(|other| other.distance(**corner) < 1e-5)
+                    })
+                    .count();
+                assert_eq!(shared, 2, "icoface {icoface_id}");
+            }
         }
-        if ICOFACE_DELTILLE_WIDTH >= 4 {
-            assert_eq!(IcoFace::exposed_deltille_id(3, DeltilleFaceSocket::NW), 12);
+    }
+
+    #[test]
+    fn slot_corners_meet_their_neighbors_across_every_edge() {
+        for (frequency, layout) in [1, 2, 3, 5]
+            .into_iter()
+            .flat_map(|frequency| LAYOUTS.map(|layout| (frequency, layout)))
+        {
+            let icosahedron = Icosahedron::with_layout(frequency, layout);
+            for slot_id in icosahedron.deltille_slot_ids() {
+                let slot = icosahedron.get_deltille_slot(&slot_id);
+                // corners run counter-clockwise and sockets clockwise, so socket `i` is
+                // the edge from corner `1 - i` to corner `2 - i`
+                let edge = |corners: [Vec3; 3], socket: usize| {
+                    return [corners[(4 - socket) % 3], corners[(5 - socket) % 3]];
+                };
+                for (socket, connection) in slot.connections.iter().enumerate() {
+                    let neighbor =
+                        icosahedron.get_deltille_slot(&connection.target_deltille_coordinates);
+                    let [a, b] = edge(slot.corners, socket);
+                    let [c, d] = edge(neighbor.corners, connection.target_socket.index());
+                    // the neighbor reads the edge the other way
+                    assert!(
+                        a.distance(d) < 1e-5 && b.distance(c) < 1e-5,
+                        "frequency {frequency}, {layout:?}, {slot_id} socket {socket}"
+                    );
+                }
+                for corner in slot.sphere_corners() {
+                    assert!((corner.length() - 1.0).abs() < 1e-5);
+                }
+                let [a, b, c] = slot.corners;
+                assert!((b - a).cross(c - a).dot(slot.direction()) > 0.0);
+            }
         }
     }
 
     #[test]
-    fn new_icosahedron_works() {
-        let icosahedron = Icosahedron::new(&from_fn(|_| HashSet::new()));
-        assert_eq!(icosahedron.icofaces.len(), 20);
+    fn slot_at_finds_the_slot_under_every_point() {
+        for (frequency, layout) in [1, 2, 3, 5, 16]
+            .into_iter()
+            .flat_map(|frequency| LAYOUTS.map(|layout| (frequency, layout)))
+        {
+            let icosahedron = Icosahedron::with_layout(frequency, layout);
+            for slot_id in icosahedron.deltille_slot_ids() {
+                let slot = icosahedron.get_deltille_slot(&slot_id);
+                for weights in [[1.0 / 3.0; 3], [0.1, 0.2, 0.7], [0.8, 0.15, 0.05]] {
+                    let point = (0..3).fold(Vec3::ZERO, |point, corner| {
+                        point + slot.corners[corner] * weights[corner]
+                    });
+                    // any distance along the direction lands in the same place
+                    let location = icosahedron.slot_at(point * 7.0).unwrap();
+                    assert_eq!(
+                        location.slot_id, slot_id,
+                        "frequency {frequency}, {layout:?}"
+                    );
+                    for (found, weight) in location.barycentric.into_iter().zip(weights) {
+                        assert!(
+                            (found - weight).abs() < 1e-3,
+                            "frequency {frequency}, {layout:?}"
+                        );
+                    }
+                }
+            }
+        }
     }
 
+    #[test]
+    fn slot_at_handles_poles_and_zero() {
+        let icosahedron = Icosahedron::new(FREQUENCY);
+        assert_eq!(icosahedron.slot_at(Vec3::ZERO), None);
+
+        let north = icosahedron.slot_at_lat_long(FRAC_PI_2, 0.0);
+        assert!(north.slot_id.icoface_id < 5);
+        let south = icosahedron.slot_at_lat_long(-FRAC_PI_2, 0.0);
+        assert!(south.slot_id.icoface_id >= 15);
+        for location in [north, south] {
+            assert!(location
+                .barycentric
+                .iter()
+                .all(|weight| (-1e-4..=1.0 + 1e-4).contains(weight)));
+        }
+        // longitude 0 is the meridian of the first upper ring vertex
+        assert!(lat_long_direction(0.0, 0.0).distance(Vec3::X) < 1e-6);
+    }
 }
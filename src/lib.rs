@@ -0,0 +1,54 @@
+//! Generates icosphere textures by wave function collapse over a net of deltilles
+//! (triangular tiles).
+//!
+//! - `domain`: the bitset of option ids each slot still allows
+//! - `icosahedron`: the 20 faces, their deltille slots and how slots connect across
+//!   face edges
+//! - `net_layout`: where the faces sit in the unfolded net
+//! - `tileset`: loading deltille definitions and socket rules from JSON
+//! - `wave_function_collapse`: the seeded solver
+//! - `export`: rendering a solved net into an image
+//! - `mesh`: triangle meshes of the icosahedron, UV-mapped into that image
+//! - `mesh_export`: writing those meshes as `.obj` or `.glb` files
+//! - `projection`: resampling the image into equirectangular or cubemap textures
+//! - `batch`: solving many seeds in parallel, with a report of how each went
+//!
+//! ```
+//! use ico_generator::{export, Tileset, WfcConfig};
+//!
+//! let tileset = Tileset::load("assets/deltilles.json").unwrap();
+//! let config = WfcConfig { seed: 42, ..Default::default() };
+//! let state = ico_generator::generate(tileset.deltille_options(), config).unwrap();
+//! let tile_images = export::load_tile_images(&state, "assets".as_ref()).unwrap();
+//! let net = export::render_net(&state, &tile_images);
+//! ```
+
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
+
+pub mod batch;
+pub mod config_constants;
+pub mod domain;
+pub mod export;
+pub mod icosahedron;
+pub mod mesh;
+pub mod mesh_export;
+pub mod net_layout;
+pub mod projection;
+pub mod tileset;
+pub mod wave_function_collapse;
+
+pub use domain::Domain;
+pub use icosahedron::{Deltille, DeltilleSlot, DeltilleSlotId, IcoFace, Icosahedron, SlotLocation};
+pub use tileset::{DeltilleOptions, SocketRules, Tileset, TilesetError};
+pub use wave_function_collapse::{WfcConfig, WfcError, WfcState};
+
+/// Solves a tiling to completion; the result is the same for the same options and config
+pub fn generate(
+    deltille_options: DeltilleOptions,
+    config: WfcConfig,
+) -> Result<WfcState, WfcError> {
+    let mut state = WfcState::new(deltille_options, config);
+    state.run()?;
+    return Ok(state);
+}
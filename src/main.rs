@@ -1,54 +1,86 @@
-mod config_constants;
-mod graphics;
-mod icosahedron;
-mod wave_function_collapse;
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
 
-use std::collections::HashSet;
+mod cli;
+#[cfg(feature = "viewer")]
+mod graphics;
+#[cfg(feature = "viewer")]
+mod playback;
+#[cfg(feature = "viewer")]
+mod preview;
+#[cfg(feature = "viewer")]
+mod viewer;
 
-use bevy::prelude::*;
-use bevy::window::WindowResolution;
-use config_constants::*;
-use icosahedron::Icosahedron;
-use graphics::*;
-// use wave_function_collapse::*;
+use clap::Parser;
+use cli::{Cli, Command};
+#[cfg(not(feature = "viewer"))]
+use cli::{MeshArg, PlaybackArgs, SolverArgs};
+#[cfg(feature = "viewer")]
+use viewer::view;
 
 fn main() {
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "Icosphere Texture Generator".to_string(),
-                        resolution: WindowResolution::new(
-                            WINDOW_GRID_WIDTH as f32 * VIEW_SCALE,
-                            WINDOW_GRID_HEIGHT as f32 * VIEW_SCALE,
-                        ),
-                        ..default()
-                    }),
-                    ..default()
-                })
-                .set(ImagePlugin::default_nearest()),
-        )
-        .insert_resource(Icosahedron::new(&[HashSet::new(), HashSet::new()]))
-        // .insert_resource(WfcState::new())
-        // .add_systems(Update, iterate_wfc.run_if(not_yet_complete))
-        .add_systems(Startup, setup)
-        .add_systems(Update, draw_debug)
-        .run();
+    let command = Cli::parse().command.unwrap_or_else(|| {
+        // no subcommand is `view` with its defaults
+        return Cli::parse_from(["ico_generator", "view"]).command.unwrap();
+    });
+    let result = match command {
+        Command::Generate {
+            solver,
+            seed,
+            out,
+            mesh,
+            projection,
+        } => {
+            let seed = seed.unwrap_or_else(random_seed);
+            cli::generate(&solver, seed, &out, &mesh, &projection)
+                .map(|()| println!("wrote {}", out.display()))
+        }
+        Command::Batch {
+            solver,
+            count,
+            first_seed,
+            out_dir,
+            threads,
+        } => cli::batch(&solver, count, first_seed, &out_dir, threads).and_then(|failures| {
+            if failures > 0 {
+                return Err(format!("{failures} of {count} seeds failed"));
+            }
+            return Ok(());
+        }),
+        Command::View {
+            solver,
+            seed,
+            three_d,
+            mesh,
+            playback,
+        } => view(
+            &solver,
+            seed.unwrap_or_else(random_seed),
+            three_d,
+            mesh,
+            &playback,
+        ),
+    };
+    if let Err(error) = result {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
 }
 
-fn setup(mut commands: Commands, mut gizmoConfig: ResMut<GizmoConfig>) {
-    commands.spawn(Camera2dBundle {
-        projection: OrthographicProjection {
-            scale: 1.0 / VIEW_SCALE,
-            ..default()
-        },
-        transform: Transform::from_xyz(
-            WINDOW_GRID_WIDTH as f32 / 2.0,
-            WINDOW_GRID_HEIGHT as f32 / 2.0,
-            1.0,
-        ),
-        ..default()
-    });
-    gizmoConfig.line_width = 1.5;
+fn random_seed() -> u64 {
+    let seed = rand::random();
+    println!("seed: {seed}");
+    return seed;
+}
+
+/// The window needs the `viewer` feature's parts of bevy
+#[cfg(not(feature = "viewer"))]
+fn view(
+    _solver: &SolverArgs,
+    _seed: u64,
+    _three_d: bool,
+    _mesh: MeshArg,
+    _playback: &PlaybackArgs,
+) -> Result<(), String> {
+    return Err("built without the viewer; rebuild with `--features viewer`".to_string());
 }
@@ -0,0 +1,148 @@
+//! Triangle meshes of the icosahedron, UV-mapped into the net image from `export`.
+//!
+//! Every triangle has its own three vertices, so UVs can jump across the net's seams.
+//! Triangles are wound counter-clockwise seen from outside.
+
+use bevy::prelude::{Vec2, Vec3};
+
+use crate::icosahedron::{DeltilleSlotId, Icosahedron};
+
+/// How finely the mesh follows the deltilles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshDetail {
+    /// one flat triangle per icoface
+    #[default]
+    Icofaces,
+
+    /// one triangle per deltille slot, lying flat on its icoface
+    Deltilles,
+
+    /// one triangle per deltille slot, with corners pushed out onto the unit sphere
+    Sphere,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+
+    /// into the net image, with v pointing down from the top of the image
+    pub uvs: Vec<Vec2>,
+
+    /// the deltille slot of each triangle, or `None` for whole icofaces
+    pub triangle_slots: Vec<Option<DeltilleSlotId>>,
+}
+
+impl NetMesh {
+    pub fn new(icosahedron: &Icosahedron, detail: MeshDetail) -> Self {
+        let mut mesh = NetMesh::default();
+        let net_size = Vec2::new(
+            icosahedron.net_grid_width() as f32,
+            icosahedron.net_grid_height() as f32,
+        );
+        for icoface_id in 0..icosahedron.icofaces.len() {
+            let net_corners = icosahedron.icoface_net_corners(icoface_id);
+            let corners = icosahedron.icoface_corners(icoface_id);
+            if detail == MeshDetail::Icofaces {
+                mesh.push_triangle(net_corners, corners, net_size, None);
+                continue;
+            }
+            for deltille_id in 0..icosahedron.icoface_deltille_count() {
+                let slot_id = DeltilleSlotId {
+                    icoface_id,
+                    deltille_id,
+                };
+                let deltille_net_corners = icosahedron.deltille_net_corners(&slot_id);
+                let slot = icosahedron.get_deltille_slot(&slot_id);
+                let deltille_corners = if detail == MeshDetail::Sphere {
+                    slot.sphere_corners()
+                } else {
+                    slot.corners
+                };
+                mesh.push_triangle(
+                    deltille_net_corners,
+                    deltille_corners,
+                    net_size,
+                    Some(slot_id),
+                );
+            }
+        }
+        if detail == MeshDetail::Sphere {
+            mesh.normals = mesh.positions.clone();
+        }
+        return mesh;
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        return self.triangle_slots.len();
+    }
+
+    fn push_triangle(
+        &mut self,
+        net_corners: [Vec2; 3],
+        corners: [Vec3; 3],
+        net_size: Vec2,
+        slot_id: Option<DeltilleSlotId>,
+    ) {
+        let [a, b, c] = corners;
+        let normal = (b - a).cross(c - a).normalize();
+        for (net_corner, corner) in net_corners.into_iter().zip(corners) {
+            self.positions.push(corner);
+            self.normals.push(normal);
+            self.uvs.push(Vec2::new(
+                net_corner.x / net_size.x,
+                1.0 - net_corner.y / net_size.y,
+            ));
+        }
+        self.triangle_slots.push(slot_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::icosahedron::Icosahedron;
+
+    use super::{MeshDetail, NetMesh};
+
+    #[test]
+    fn meshes_have_a_triangle_per_face_or_slot() {
+        let icosahedron = Icosahedron::new(3);
+        assert_eq!(
+            NetMesh::new(&icosahedron, MeshDetail::Icofaces).triangle_count(),
+            20
+        );
+        for detail in [MeshDetail::Deltilles, MeshDetail::Sphere] {
+            let mesh = NetMesh::new(&icosahedron, detail);
+            assert_eq!(mesh.triangle_count(), 180);
+            assert_eq!(mesh.positions.len(), 3 * 180);
+            assert_eq!(mesh.uvs.len(), mesh.positions.len());
+            assert_eq!(mesh.normals.len(), mesh.positions.len());
+        }
+    }
+
+    #[test]
+    fn sphere_meshes_lie_on_the_unit_sphere() {
+        let icosahedron = Icosahedron::new(4);
+        let mesh = NetMesh::new(&icosahedron, MeshDetail::Sphere);
+        assert!(mesh
+            .positions
+            .iter()
+            .all(|position| (position.length() - 1.0).abs() < 1e-5));
+
+        // flat deltilles sit inside the sphere except at the icosahedron's vertices
+        let flat = NetMesh::new(&icosahedron, MeshDetail::Deltilles);
+        assert!(flat
+            .positions
+            .iter()
+            .all(|position| position.length() < 1.0 + 1e-5));
+    }
+
+    #[test]
+    fn uvs_stay_inside_the_net() {
+        let icosahedron = Icosahedron::new(2);
+        let mesh = NetMesh::new(&icosahedron, MeshDetail::Deltilles);
+        for uv in mesh.uvs {
+            assert!((0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y));
+        }
+    }
+}
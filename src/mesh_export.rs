@@ -0,0 +1,473 @@
+//! Writes the icosahedron as a ready-to-load mesh, textured with the net from `export`.
+//!
+//! `.obj` files come with a `.mtl` material and the texture as a `.png` next to them.
+//! Binary glTF (`.glb`) files embed the texture unless told to reference a `.png`
+//! instead. Both use the vertices and UVs of `mesh::NetMesh`, so every triangle has its
+//! own three vertices.
+//!
+//! Tile ids say which tile each triangle shows: the index of its image among the
+//! tileset's deltilles in file order, which is the deltille definition's index when
+//! every definition has its own image. glTF stores them in a `_TILE_ID` float vertex
+//! attribute, the same on all three vertices of a triangle, and OBJ groups faces as
+//! `g tile_<id>`. Undecided slots, and whole icofaces, get -1 and `g undecided`.
+
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+
+use image::{ImageOutputFormat, RgbaImage};
+use serde_json::{json, Value};
+
+use crate::export::{bleed_gutter, load_tile_images, render_net, ExportError};
+use crate::icosahedron::ArrayIndex;
+use crate::mesh::{MeshDetail, NetMesh};
+use crate::wave_function_collapse::WfcState;
+
+/// How a `.glb` file carries its texture; `.obj` files always reference it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureMode {
+    #[default]
+    Embedded,
+
+    /// written as a `.png` with the same name as the mesh
+    Referenced,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshExportOptions {
+    pub detail: MeshDetail,
+    pub tile_ids: bool,
+    pub texture: TextureMode,
+
+    /// pixels of bleed around each icoface in the texture, see `bleed_gutter`
+    pub gutter: usize,
+}
+
+impl Default for MeshExportOptions {
+    fn default() -> Self {
+        return MeshExportOptions {
+            detail: MeshDetail::Deltilles,
+            tile_ids: false,
+            texture: TextureMode::Embedded,
+            gutter: 0,
+        };
+    }
+}
+
+/// Loads the tile images, renders the net and writes the mesh to `path` as `.obj` or
+/// `.glb`, following the file extension
+pub fn export_mesh(
+    state: &WfcState,
+    image_dir: &Path,
+    path: &Path,
+    options: MeshExportOptions,
+) -> Result<(), ExportError> {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    if !matches!(extension, Some("obj") | Some("glb")) {
+        return Err(ExportError::UnsupportedFormat(path.to_path_buf()));
+    }
+    let mut net = render_net(state, &load_tile_images(state, image_dir)?);
+    bleed_gutter(&state.icosahedron, &mut net, options.gutter);
+    let mesh = NetMesh::new(&state.icosahedron, options.detail);
+    let tile_ids = options.tile_ids.then(|| triangle_tile_ids(state, &mesh));
+    let texture_path = path.with_extension("png");
+    let texture_name = file_name(&texture_path);
+
+    if extension == Some("obj") {
+        save_texture(&net, &texture_path)?;
+        let material_path = path.with_extension("mtl");
+        write_file(&material_path, obj_material(&texture_name).as_bytes())?;
+        let mut obj = Vec::new();
+        write_obj(
+            &mesh,
+            tile_ids.as_deref(),
+            &file_name(&material_path),
+            &mut obj,
+        )
+        .expect("writing to memory does not fail");
+        return write_file(path, &obj);
+    }
+
+    let texture = match options.texture {
+        TextureMode::Embedded => GlbTexture::Embedded(png_bytes(&net, path)?),
+        TextureMode::Referenced => {
+            save_texture(&net, &texture_path)?;
+            GlbTexture::Referenced(texture_name)
+        }
+    };
+    return write_file(path, &glb_bytes(&mesh, tile_ids.as_deref(), texture));
+}
+
+/// The tile id of each triangle of `mesh`, or `None` where no tile has been chosen
+pub fn triangle_tile_ids(state: &WfcState, mesh: &NetMesh) -> Vec<Option<usize>> {
+    let mut image_paths: Vec<&String> = Vec::new();
+    for deltille in state.deltille_options.deltilles.iter().flatten() {
+        if !image_paths.contains(&&deltille.image_path) {
+            image_paths.push(&deltille.image_path);
+        }
+    }
+    return mesh
+        .triangle_slots
+        .iter()
+        .map(|slot_id| {
+            let slot_id = slot_id.as_ref()?;
+            let slot = state.icosahedron.get_deltille_slot(slot_id);
+            let domain = state.domain(slot_id);
+            if domain.len() != 1 {
+                return None;
+            }
+            let option_id = domain.iter().next().unwrap();
+            let deltille = &state.deltille_options.deltilles[slot.orientation.index()][option_id];
+            return image_paths
+                .iter()
+                .position(|image_path| **image_path == deltille.image_path);
+        })
+        .collect();
+}
+
+/// Writes `mesh` as Wavefront OBJ using the material `newmtl net` from `material_file`
+pub fn write_obj(
+    mesh: &NetMesh,
+    tile_ids: Option<&[Option<usize>]>,
+    material_file: &str,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "mtllib {material_file}")?;
+    writeln!(out, "o icosphere")?;
+    for position in mesh.positions.iter() {
+        writeln!(out, "v {} {} {}", position.x, position.y, position.z)?;
+    }
+    // OBJ's v points up from the bottom of the image
+    for uv in mesh.uvs.iter() {
+        writeln!(out, "vt {} {}", uv.x, 1.0 - uv.y)?;
+    }
+    for normal in mesh.normals.iter() {
+        writeln!(out, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+    }
+    writeln!(out, "usemtl net")?;
+    let mut group = None;
+    for triangle in 0..mesh.triangle_count() {
+        if let Some(tile_ids) = tile_ids {
+            if group != Some(tile_ids[triangle]) {
+                group = Some(tile_ids[triangle]);
+                match tile_ids[triangle] {
+                    Some(tile_id) => writeln!(out, "g tile_{tile_id}")?,
+                    None => writeln!(out, "g undecided")?,
+                }
+            }
+        }
+        // OBJ indices start at 1
+        let [a, b, c] = [1, 2, 3].map(|corner| 3 * triangle + corner);
+        writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+    }
+    return Ok(());
+}
+
+/// Where a `.glb` file finds its texture
+pub enum GlbTexture {
+    /// PNG bytes stored in the file's binary chunk
+    Embedded(Vec<u8>),
+
+    /// URI of a PNG next to the file
+    Referenced(String),
+}
+
+// glTF constants
+const FLOAT: u32 = 5126;
+const ARRAY_BUFFER: u32 = 34962;
+const NEAREST: u32 = 9728;
+const CLAMP_TO_EDGE: u32 = 33071;
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const JSON_CHUNK: u32 = 0x4E4F_534A;
+const BIN_CHUNK: u32 = 0x004E_4942;
+
+/// Binary glTF 2.0 of `mesh` with one unlit, textured material
+pub fn glb_bytes(
+    mesh: &NetMesh,
+    tile_ids: Option<&[Option<usize>]>,
+    texture: GlbTexture,
+) -> Vec<u8> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut buffer_views: Vec<Value> = Vec::new();
+    let mut accessors: Vec<Value> = Vec::new();
+    let mut attributes = serde_json::Map::new();
+
+    let mut add_attribute = |name: &str, kind: &str, components: Vec<Vec<f32>>| {
+        let offset = buffer.len();
+        for value in components.iter().flatten() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": buffer.len() - offset,
+            "target": ARRAY_BUFFER,
+        }));
+        let mut accessor = json!({
+            "bufferView": buffer_views.len() - 1,
+            "componentType": FLOAT,
+            "count": components.len(),
+            "type": kind,
+        });
+        if name == "POSITION" {
+            // positions need bounds
+            let bound = |pick: fn(f32, f32) -> f32| {
+                return (0..3)
+                    .map(|axis| {
+                        components
+                            .iter()
+                            .map(|component| component[axis])
+                            .reduce(pick)
+                            .unwrap_or(0.0)
+                    })
+                    .collect::<Vec<f32>>();
+            };
+            accessor["min"] = json!(bound(f32::min));
+            accessor["max"] = json!(bound(f32::max));
+        }
+        accessors.push(accessor);
+        attributes.insert(name.to_string(), json!(accessors.len() - 1));
+    };
+
+    add_attribute(
+        "POSITION",
+        "VEC3",
+        mesh.positions
+            .iter()
+            .map(|position| position.to_array().to_vec())
+            .collect(),
+    );
+    add_attribute(
+        "NORMAL",
+        "VEC3",
+        mesh.normals
+            .iter()
+            .map(|normal| normal.to_array().to_vec())
+            .collect(),
+    );
+    add_attribute(
+        "TEXCOORD_0",
+        "VEC2",
+        mesh.uvs.iter().map(|uv| uv.to_array().to_vec()).collect(),
+    );
+    if let Some(tile_ids) = tile_ids {
+        add_attribute(
+            "_TILE_ID",
+            "SCALAR",
+            tile_ids
+                .iter()
+                .flat_map(|tile_id| {
+                    let tile_id = tile_id.map_or(-1.0, |tile_id| tile_id as f32);
+                    return [vec![tile_id], vec![tile_id], vec![tile_id]];
+                })
+                .collect(),
+        );
+    }
+
+    let image = match texture {
+        GlbTexture::Embedded(png) => {
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": buffer.len(),
+                "byteLength": png.len(),
+            }));
+            buffer.extend(png);
+            json!({ "bufferView": buffer_views.len() - 1, "mimeType": "image/png" })
+        }
+        GlbTexture::Referenced(uri) => json!({ "uri": uri }),
+    };
+    pad(&mut buffer, 0);
+
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "ico_generator" },
+        "extensionsUsed": ["KHR_materials_unlit"],
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": "icosphere", "mesh": 0 }],
+        "meshes": [{ "primitives": [{ "attributes": attributes, "material": 0 }] }],
+        "materials": [{
+            "name": "net",
+            "pbrMetallicRoughness": {
+                "baseColorTexture": { "index": 0 },
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            },
+            "alphaMode": "MASK",
+            "extensions": { "KHR_materials_unlit": {} },
+        }],
+        "textures": [{ "sampler": 0, "source": 0 }],
+        // deltilles are pixel art
+        "samplers": [{
+            "magFilter": NEAREST,
+            "minFilter": NEAREST,
+            "wrapS": CLAMP_TO_EDGE,
+            "wrapT": CLAMP_TO_EDGE,
+        }],
+        "images": [image],
+        "buffers": [{ "byteLength": buffer.len() }],
+        "bufferViews": buffer_views,
+        "accessors": accessors,
+    });
+    let mut json = serde_json::to_vec(&gltf).expect("glTF JSON serializes");
+    pad(&mut json, b' ');
+
+    let mut glb = Vec::with_capacity(12 + 8 + json.len() + 8 + buffer.len());
+    let length = 12 + 8 + json.len() + 8 + buffer.len();
+    for word in [GLB_MAGIC, 2, length as u32, json.len() as u32, JSON_CHUNK] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend(json);
+    for word in [buffer.len() as u32, BIN_CHUNK] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend(buffer);
+    return glb;
+}
+
+/// GLB chunks are aligned to four bytes
+// `usize::is_multiple_of` would need Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn pad(bytes: &mut Vec<u8>, padding: u8) {
+    while bytes.len() % 4 != 0 {
+        bytes.push(padding);
+    }
+}
+
+fn obj_material(texture_file: &str) -> String {
+    return format!("newmtl net\nKa 1 1 1\nKd 1 1 1\nKs 0 0 0\nillum 1\nmap_Kd {texture_file}\n");
+}
+
+fn png_bytes(net: &RgbaImage, path: &Path) -> Result<Vec<u8>, ExportError> {
+    let mut png = Cursor::new(Vec::new());
+    net.write_to(&mut png, ImageOutputFormat::Png)
+        .map_err(|error| ExportError::Save {
+            path: path.to_path_buf(),
+            error,
+        })?;
+    return Ok(png.into_inner());
+}
+
+fn save_texture(net: &RgbaImage, path: &Path) -> Result<(), ExportError> {
+    return net.save(path).map_err(|error| ExportError::Save {
+        path: path.to_path_buf(),
+        error,
+    });
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), ExportError> {
+    return fs::write(path, contents).map_err(|error| ExportError::Write {
+        path: path.to_path_buf(),
+        error,
+    });
+}
+
+/// Materials and images are referenced relative to the mesh file
+fn file_name(path: &Path) -> String {
+    return path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use serde_json::Value;
+
+    use crate::config_constants::DELTILLES_PATH;
+    use crate::mesh::{MeshDetail, NetMesh};
+    use crate::tileset::Tileset;
+    use crate::wave_function_collapse::{WfcConfig, WfcState};
+
+    use super::{
+        export_mesh, glb_bytes, triangle_tile_ids, write_obj, GlbTexture, MeshExportOptions,
+        TextureMode,
+    };
+
+    fn solved(frequency: usize) -> WfcState {
+        let deltille_options = Tileset::load(DELTILLES_PATH).unwrap().deltille_options();
+        let config = WfcConfig {
+            frequency,
+            ..WfcConfig::default()
+        };
+        let mut state = WfcState::new(deltille_options, config);
+        state.run().unwrap();
+        return state;
+    }
+
+    fn word(bytes: &[u8], offset: usize) -> u32 {
+        return u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    }
+
+    #[test]
+    fn obj_has_a_face_per_slot_grouped_by_tile() {
+        let state = solved(2);
+        let mesh = NetMesh::new(&state.icosahedron, MeshDetail::Deltilles);
+        let tile_ids = triangle_tile_ids(&state, &mesh);
+        assert!(tile_ids.iter().all(Option::is_some));
+
+        let mut obj = Vec::new();
+        write_obj(&mesh, Some(&tile_ids), "planet.mtl", &mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let count = |prefix: &str| obj.lines().filter(|line| line.starts_with(prefix)).count();
+        assert_eq!(count("f "), 80);
+        assert_eq!(count("v "), 240);
+        assert_eq!(count("vt "), 240);
+        assert!(count("g tile_") > 0);
+        assert!(obj.starts_with("mtllib planet.mtl\n"));
+    }
+
+    #[test]
+    fn glb_holds_the_mesh_and_its_texture() {
+        let state = solved(2);
+        let mesh = NetMesh::new(&state.icosahedron, MeshDetail::Sphere);
+        let tile_ids = triangle_tile_ids(&state, &mesh);
+        let glb = glb_bytes(&mesh, Some(&tile_ids), GlbTexture::Embedded(vec![1, 2, 3]));
+
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(word(&glb, 4), 2);
+        assert_eq!(word(&glb, 8) as usize, glb.len());
+        let json_length = word(&glb, 12) as usize;
+        assert_eq!(json_length % 4, 0);
+        let gltf: Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        let bin_length = word(&glb, 20 + json_length) as usize;
+        assert_eq!(gltf["buffers"][0]["byteLength"], bin_length);
+        assert_eq!(28 + json_length + bin_length, glb.len());
+
+        let attributes = &gltf["meshes"][0]["primitives"][0]["attributes"];
+        for attribute in ["POSITION", "NORMAL", "TEXCOORD_0", "_TILE_ID"] {
+            let accessor = &gltf["accessors"][attributes[attribute].as_u64().unwrap() as usize];
+            assert_eq!(accessor["count"], 240, "{attribute}");
+        }
+        assert_eq!(gltf["images"][0]["mimeType"], "image/png");
+    }
+
+    #[test]
+    fn exports_obj_and_glb_files() {
+        let state = solved(2);
+        let image_dir = Path::new(DELTILLES_PATH).parent().unwrap();
+        let out_dir = std::env::temp_dir().join(format!("ico_mesh_{}", std::process::id()));
+        fs::create_dir_all(&out_dir).unwrap();
+
+        let options = MeshExportOptions::default();
+        export_mesh(&state, image_dir, &out_dir.join("planet.obj"), options).unwrap();
+        for file in ["planet.obj", "planet.mtl", "planet.png"] {
+            assert!(out_dir.join(file).is_file(), "{file}");
+        }
+
+        export_mesh(&state, image_dir, &out_dir.join("moon.glb"), options).unwrap();
+        assert!(out_dir.join("moon.glb").is_file());
+        assert!(!out_dir.join("moon.png").exists());
+        let referenced = MeshExportOptions {
+            texture: TextureMode::Referenced,
+            ..options
+        };
+        export_mesh(&state, image_dir, &out_dir.join("moon.glb"), referenced).unwrap();
+        assert!(out_dir.join("moon.png").is_file());
+
+        assert!(export_mesh(&state, image_dir, &out_dir.join("planet.stl"), options).is_err());
+        fs::remove_dir_all(out_dir).unwrap();
+    }
+}
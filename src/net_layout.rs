@@ -0,0 +1,345 @@
+//! Where the 20 icofaces sit in the unfolded net, and which way round.
+//!
+//! Icoface ids always name the same faces of the icosahedron (see
+//! `Icosahedron::icoface_corners`); a layout only moves them around the net, and may
+//! turn them in steps of 60°. A face turned by an odd number of steps points the other
+//! way, so its deltilles do too. How faces connect across their edges is worked out
+//! from the layout, so any layout tiles the sphere the same way.
+
+use bevy::prelude::Vec2;
+
+use crate::icosahedron::{triangle_corners, VerticalOrientation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetLayout {
+    /// five columns of staggered rows, 5½ × 3 icofaces:
+    ///
+    /// ```text
+    /// ∧   ∧   ∧   ∧   ∧
+    /// ∨ ∧ ∨ ∧ ∨ ∧ ∨ ∧ ∨ ∧
+    ///   ∨   ∨   ∨   ∨   ∨
+    /// ```
+    #[default]
+    Standard,
+
+    /// the standard net with the south cap moved into the gaps of the north cap,
+    /// 5½ × 2 icofaces with no space left over:
+    ///
+    /// ```text
+    /// ∧ ∨ ∧ ∨ ∧ ∨ ∧ ∨ ∧ ∨
+    /// ∨ ∧ ∨ ∧ ∨ ∧ ∨ ∧ ∨ ∧
+    /// ```
+    TwoStrip,
+
+    /// every icoface alone in a cell of a 5 × 4 grid, in id order from the top left,
+    /// with `padding` pixels between it and the edges of its cell
+    Atlas { padding: usize },
+
+    /// an irregular unfolding in the style of Fuller's Dymaxion map: the middle band with
+    /// the caps' faces spread along both sides, some of them turned, 6 × 3 icofaces:
+    ///
+    /// ```text
+    ///       ∨ ∧ ∨     ∧ ∨ ∧
+    ///   ∧ ∨ ∧ ∨ ∧ ∨ ∧ ∨ ∧
+    /// ∧ ∨   ∨     ∧ ∨
+    /// ```
+    Dymaxion,
+}
+
+/// How an icoface is placed in the net
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IcoFacePlacement {
+    /// center in grid pixels
+    pub position: Vec2,
+    pub orientation: VerticalOrientation,
+
+    /// indices into `icosahedron_vertices` of the corners, in the order of
+    /// `triangle_corners`
+    pub vertices: [usize; 3],
+}
+
+/// Dymaxion placements by icoface id: center x in half icoface widths, center y in half
+/// icoface heights and sixths of a turn counter-clockwise from the standard net
+const DYMAXION: [(usize, usize, usize); 20] = [
+    (4, 5, 5),
+    (5, 5, 0),
+    (6, 5, 1),
+    (9, 5, 0),
+    (10, 5, 1),
+    (3, 3, 0),
+    (5, 3, 0),
+    (7, 3, 0),
+    (9, 3, 0),
+    (11, 5, 3),
+    (4, 3, 0),
+    (6, 3, 0),
+    (8, 3, 0),
+    (10, 3, 0),
+    (2, 3, 0),
+    (4, 1, 0),
+    (7, 1, 3),
+    (8, 1, 0),
+    (1, 1, 3),
+    (2, 1, 0),
+];
+
+impl NetLayout {
+    /// Placements of the icofaces by id, for icofaces of the given size in grid pixels
+    pub fn placements(&self, icoface_width: f32, icoface_height: f32) -> [IcoFacePlacement; 20] {
+        let place = |icoface_id: usize, x_halves: usize, y_halves: usize, turns: usize| {
+            let position = Vec2::new(
+                x_halves as f32 * icoface_width / 2.0,
+                y_halves as f32 * icoface_height / 2.0,
+            );
+            return turned(icoface_id, position, turns);
+        };
+        return std::array::from_fn(|icoface_id| {
+            let column = icoface_id % 5;
+            let row = icoface_id / 5;
+            return match self {
+                NetLayout::Standard => {
+                    let x_halves = [
+                        2 * column + 1,
+                        2 * column + 1,
+                        2 * column + 2,
+                        2 * column + 2,
+                    ];
+                    place(icoface_id, x_halves[row], [5, 3, 3, 1][row], 0)
+                }
+                NetLayout::TwoStrip => {
+                    let x_halves = [
+                        2 * column + 1,
+                        2 * column + 1,
+                        2 * column + 2,
+                        2 * column + 2,
+                    ];
+                    place(icoface_id, x_halves[row], [3, 1, 1, 3][row], 0)
+                }
+                NetLayout::Atlas { padding } => {
+                    let cell = Vec2::new(icoface_width, icoface_height) + 2.0 * *padding as f32;
+                    let position =
+                        Vec2::new((column as f32 + 0.5) * cell.x, (3.5 - row as f32) * cell.y);
+                    turned(icoface_id, position, 0)
+                }
+                NetLayout::Dymaxion => {
+                    let (x_halves, y_halves, turns) = DYMAXION[icoface_id];
+                    place(icoface_id, x_halves, y_halves, turns)
+                }
+            };
+        });
+    }
+
+    /// Width and height of the net in grid pixels: everything the icofaces cover, with
+    /// the same margin right and top as there is left and bottom
+    pub fn net_size(&self, icoface_width: f32, icoface_height: f32) -> (usize, usize) {
+        let corners: Vec<Vec2> = self
+            .placements(icoface_width, icoface_height)
+            .iter()
+            .flat_map(|placement| {
+                triangle_corners(
+                    placement.position,
+                    placement.orientation,
+                    icoface_width,
+                    icoface_height,
+                )
+            })
+            .collect();
+        let min = corners.iter().copied().reduce(Vec2::min).unwrap();
+        let max = corners.iter().copied().reduce(Vec2::max).unwrap();
+        let size = (min + max).round();
+        return (size.x as usize, size.y as usize);
+    }
+}
+
+/// The icoface's placement at `position`, turned `turns` sixths of a turn
+/// counter-clockwise from how it sits in the standard net.
+///
+/// A sixth of a turn points the face the other way and lines its corners up with the
+/// next orientation's in the same order; a third of a turn moves every corner on to
+/// where the next one was.
+fn turned(icoface_id: usize, position: Vec2, turns: usize) -> IcoFacePlacement {
+    let vertices = standard_vertices(icoface_id);
+    let mut orientation = [VerticalOrientation::Up, VerticalOrientation::Down][icoface_id / 5 % 2];
+    if turns % 2 == 1 {
+        orientation = match orientation {
+            VerticalOrientation::Up => VerticalOrientation::Down,
+            VerticalOrientation::Down => VerticalOrientation::Up,
+        };
+    }
+    let thirds = turns / 2;
+    return IcoFacePlacement {
+        position,
+        orientation,
+        vertices: std::array::from_fn(|corner| vertices[(corner + 2 * thirds) % 3]),
+    };
+}
+
+/// The icoface's corners as they sit in the standard net
+///
+/// ```text
+///  row 0 (0-4)    ∧  north pole on top
+///  row 1 (5-9)    ∨  upper ring on top, lower ring below
+///  row 2 (10-14)  ∧  upper ring on top, lower ring below
+///  row 3 (15-19)  ∨  south pole below
+/// ```
+fn standard_vertices(icoface_id: usize) -> [usize; 3] {
+    let column = icoface_id % 5;
+    let upper = |column: usize| 1 + column % 5;
+    let lower = |column: usize| 6 + column % 5;
+    return match icoface_id / 5 {
+        0 => [upper(column), upper(column + 1), 0],
+        1 => [lower(column), upper(column + 1), upper(column)],
+        2 => [lower(column), lower(column + 1), upper(column + 1)],
+        _ => [11, lower(column + 1), lower(column)],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use crate::icosahedron::triangle_corners;
+
+    use super::NetLayout;
+
+    const LAYOUTS: [NetLayout; 4] = [
+        NetLayout::Standard,
+        NetLayout::TwoStrip,
+        NetLayout::Atlas { padding: 2 },
+        NetLayout::Dymaxion,
+    ];
+
+    #[test]
+    fn net_sizes_fit_the_layouts() {
+        let (width, height) = (16.0, 14.0);
+        assert_eq!(NetLayout::Standard.net_size(width, height), (88, 42));
+        assert_eq!(NetLayout::TwoStrip.net_size(width, height), (88, 28));
+        assert_eq!(
+            NetLayout::Atlas { padding: 2 }.net_size(width, height),
+            (100, 72)
+        );
+        assert_eq!(NetLayout::Dymaxion.net_size(width, height), (96, 42));
+    }
+
+    /// Net corners of each placed icoface, with the icosahedron vertex at each
+    fn placed_triangles(layout: NetLayout) -> Vec<([Vec2; 3], [usize; 3])> {
+        return layout
+            .placements(16.0, 14.0)
+            .iter()
+            .map(|placement| {
+                let corners =
+                    triangle_corners(placement.position, placement.orientation, 16.0, 14.0);
+                return (corners, placement.vertices);
+            })
+            .collect();
+    }
+
+    /// Whether some line between the triangles keeps their insides apart, which for two
+    /// triangles is one of their edges; touching along an edge or at a corner is apart
+    fn separated(first: &[Vec2; 3], second: &[Vec2; 3]) -> bool {
+        return [first, second].iter().any(|triangle| {
+            return (0..3).any(|corner| {
+                let edge = triangle[(corner + 1) % 3] - triangle[corner];
+                let normal = edge.perp();
+                let project = |corners: &[Vec2; 3]| {
+                    let distances = corners.map(|point| normal.dot(point - triangle[corner]));
+                    return (
+                        distances.into_iter().reduce(f32::min).unwrap(),
+                        distances.into_iter().reduce(f32::max).unwrap(),
+                    );
+                };
+                let (first_min, first_max) = project(first);
+                let (second_min, second_max) = project(second);
+                return first_max <= second_min + 1e-3 || second_max <= first_min + 1e-3;
+            });
+        });
+    }
+
+    /// Two icofaces whose net edges lie on each other, with the icosahedron vertices at
+    /// the ends of each side of the edge
+    type NetEdge = ((usize, usize), [usize; 2], [usize; 2]);
+
+    fn net_edges(layout: NetLayout) -> Vec<NetEdge> {
+        let triangles = placed_triangles(layout);
+        let mut edges = Vec::new();
+        for (a, (first, first_vertices)) in triangles.iter().enumerate() {
+            for (b, (second, second_vertices)) in triangles.iter().enumerate().skip(a + 1) {
+                let shared: Vec<(usize, usize)> = (0..3)
+                    .flat_map(|i| (0..3).map(move |j| (i, j)))
+                    .filter(|(i, j)| first[*i].distance(second[*j]) < 1e-3)
+                    .collect();
+                if let [(i, j), (k, l)] = shared[..] {
+                    edges.push((
+                        (a, b),
+                        [first_vertices[i], first_vertices[k]],
+                        [second_vertices[j], second_vertices[l]],
+                    ));
+                }
+            }
+        }
+        return edges;
+    }
+
+    #[test]
+    fn icofaces_do_not_overlap() {
+        for layout in LAYOUTS {
+            let triangles = placed_triangles(layout);
+            assert!(!separated(&triangles[0].0, &triangles[0].0));
+            for (a, (first, _)) in triangles.iter().enumerate() {
+                for (b, (second, _)) in triangles.iter().enumerate().skip(a + 1) {
+                    assert!(separated(first, second), "{layout:?}: {a} and {b} overlap");
+                }
+            }
+        }
+    }
+
+    /// Faces the two-strip net puts edge to edge without sharing that edge on the
+    /// icosahedron: the south cap fitted into the gaps of the north cap
+    const TWO_STRIP_SEAMS: [(usize, usize); 9] = [
+        (0, 15),
+        (1, 15),
+        (1, 16),
+        (2, 16),
+        (2, 17),
+        (3, 17),
+        (3, 18),
+        (4, 18),
+        (4, 19),
+    ];
+
+    #[test]
+    fn nets_unfold_along_icosahedron_edges() {
+        for layout in [
+            NetLayout::Standard,
+            NetLayout::TwoStrip,
+            NetLayout::Dymaxion,
+        ] {
+            // which face each face is joined to through the net, so far
+            let mut group: Vec<usize> = (0..20).collect();
+            let mut seams = Vec::new();
+            for (pair, first, second) in net_edges(layout) {
+                if first != second {
+                    seams.push(pair);
+                    continue;
+                }
+                let (from, to) = (group[pair.0], group[pair.1]);
+                group
+                    .iter_mut()
+                    .filter(|g| **g == to)
+                    .for_each(|g| *g = from);
+            }
+            let expected_seams = match layout {
+                NetLayout::TwoStrip => TWO_STRIP_SEAMS.to_vec(),
+                _ => Vec::new(),
+            };
+            assert_eq!(seams, expected_seams, "{layout:?}");
+            // the two-strip net is the standard net cut apart and rejoined along the seams
+            if layout != NetLayout::TwoStrip {
+                assert!(
+                    group.iter().all(|g| *g == group[0]),
+                    "{layout:?} falls apart"
+                );
+            }
+        }
+    }
+}
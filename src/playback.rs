@@ -0,0 +1,384 @@
+//! Watching the solver work: the net texture redrawn with `render_progress` after every
+//! frame's steps, and keys to control the steps.
+//!
+//! ```text
+//! space       pause or resume
+//! right       take one step, pausing first
+//! up / down   double or halve the steps per frame
+//! g           show or hide the pixel grid
+//! ```
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use image::{Rgba, RgbaImage};
+
+use ico_generator::export::{render_net, tint_deltille, TileImages};
+use ico_generator::icosahedron::ArrayIndex;
+use ico_generator::wave_function_collapse::{StepOutcome, WfcState};
+
+use crate::cli::PlaybackArgs;
+use crate::graphics::DebugOverlay;
+
+const MAX_STEPS_PER_FRAME: usize = 1 << 16;
+
+/// greys of nearly decided and fully uncertain slots in `render_progress`
+const DECIDED_SHADE: f32 = 224.0;
+const UNCERTAIN_SHADE: f32 = 64.0;
+const CHOSEN_TINT: Rgba<u8> = Rgba([255, 220, 0, 255]);
+const WAVE_TINT: Rgba<u8> = Rgba([0, 170, 255, 255]);
+const CONTRADICTION_TINT: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// Renders the net partway through a solve. Collapsed slots show their deltilles and
+/// undecided ones a grey that darkens the more uncertain they are. The slot the latest
+/// step chose at, or undid the choice at, is tinted most, the other slots it changed
+/// less the later propagation reached them, and the slot its last contradiction emptied
+/// is red.
+fn render_progress(state: &WfcState, tile_images: &TileImages) -> RgbaImage {
+    let icosahedron = &state.icosahedron;
+    let mut net = render_net(state, tile_images);
+    for (slot, domain) in state.domains.iter().enumerate() {
+        if domain.len() == 1 {
+            continue;
+        }
+        let deltille_slot = &icosahedron.deltille_slots[slot];
+        let options = state.deltille_options.deltilles[deltille_slot.orientation.index()].len();
+        // the entropy of equally weighted options, the most the slot could have
+        let most = (options as f32).ln();
+        let uncertainty = if most > 0.0 {
+            (state.entropy(slot) / most).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let shade = (DECIDED_SHADE + (UNCERTAIN_SHADE - DECIDED_SHADE) * uncertainty) as u8;
+        let color = Rgba([shade, shade, shade, 255]);
+        tint_deltille(
+            &mut net,
+            color,
+            1.0,
+            deltille_slot.position,
+            deltille_slot.orientation,
+        );
+    }
+
+    let mut tinted = vec![false; state.domains.len()];
+    if let Some(slot) = state.last_choice {
+        tinted[slot] = true;
+        let deltille_slot = &icosahedron.deltille_slots[slot];
+        tint_deltille(
+            &mut net,
+            CHOSEN_TINT,
+            0.7,
+            deltille_slot.position,
+            deltille_slot.orientation,
+        );
+    }
+    let wave_length = state.changed_slots.len() as f32;
+    for (order, slot) in state.changed_slots.iter().enumerate() {
+        if tinted[*slot] {
+            continue;
+        }
+        tinted[*slot] = true;
+        let amount = 0.6 - 0.45 * order as f32 / wave_length;
+        let deltille_slot = &icosahedron.deltille_slots[*slot];
+        tint_deltille(
+            &mut net,
+            WAVE_TINT,
+            amount,
+            deltille_slot.position,
+            deltille_slot.orientation,
+        );
+    }
+    if let Some(slot) = state.contradiction {
+        let deltille_slot = &icosahedron.deltille_slots[slot];
+        tint_deltille(
+            &mut net,
+            CONTRADICTION_TINT,
+            1.0,
+            deltille_slot.position,
+            deltille_slot.orientation,
+        );
+    }
+    return net;
+}
+
+pub fn not_yet_complete(state: Res<WfcState>) -> bool {
+    return !state.complete && !state.failed;
+}
+
+/// How fast the viewer runs the solver
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playback {
+    pub paused: bool,
+
+    /// steps to take on the next frame even while paused
+    pub queued_steps: usize,
+    pub steps_per_frame: usize,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        return Playback {
+            paused: false,
+            queued_steps: 0,
+            steps_per_frame: 1,
+        };
+    }
+}
+
+impl Playback {
+    /// Steps for this frame, taking the queued ones
+    pub fn take_steps(&mut self) -> usize {
+        let steps = if self.paused {
+            self.queued_steps
+        } else {
+            self.steps_per_frame
+        };
+        self.queued_steps = 0;
+        return steps;
+    }
+}
+
+impl PlaybackArgs {
+    pub fn playback(&self) -> Playback {
+        return Playback {
+            paused: self.paused,
+            steps_per_frame: self.steps_per_frame.max(1),
+            ..Playback::default()
+        };
+    }
+}
+
+pub fn iterate_wfc(mut state: ResMut<WfcState>, mut playback: ResMut<Playback>) {
+    for _ in 0..playback.take_steps() {
+        match state.step() {
+            StepOutcome::Collapsed(_) | StepOutcome::Backtracked(_) => {}
+            StepOutcome::Restarted => println!("restarting, attempt {}", state.restarts + 1),
+            StepOutcome::Complete => {
+                println!("wave function collapse complete");
+                return;
+            }
+            StepOutcome::Failed => {
+                println!(
+                    "wave function collapse failed after {} restarts",
+                    state.restarts
+                );
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct NetTiles(pub TileImages);
+
+/// The net texture, redrawn whenever the solver changes the tiling
+#[derive(Resource)]
+pub struct NetTexture(pub Handle<Image>);
+
+pub fn setup_net_texture(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    tiles: Res<NetTiles>,
+    state: Res<WfcState>,
+) {
+    let net = render_progress(&state, &tiles.0);
+    let texture = images.add(Image::new(
+        Extent3d {
+            width: net.width(),
+            height: net.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        net.into_raw(),
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    commands.insert_resource(NetTexture(texture));
+}
+
+/// Lays the net texture under the 2D view, which has one grid unit per pixel
+pub fn setup_net_sprite(mut commands: Commands, texture: Res<NetTexture>, state: Res<WfcState>) {
+    let icosahedron = &state.icosahedron;
+    commands.spawn(SpriteBundle {
+        texture: texture.0.clone(),
+        transform: Transform::from_xyz(
+            icosahedron.net_grid_width() as f32 / 2.0,
+            icosahedron.net_grid_height() as f32 / 2.0,
+            0.0,
+        ),
+        ..default()
+    });
+}
+
+pub fn update_net_texture(
+    state: Res<WfcState>,
+    tiles: Res<NetTiles>,
+    texture: Res<NetTexture>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    if let Some(image) = images.get_mut(&texture.0) {
+        image.data = render_progress(&state, &tiles.0).into_raw();
+    }
+}
+
+pub fn control_playback(
+    keys: Res<Input<KeyCode>>,
+    mut playback: ResMut<Playback>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+        println!("{}", if playback.paused { "paused" } else { "resumed" });
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        playback.paused = true;
+        playback.queued_steps += 1;
+    }
+    let steps_per_frame = playback.steps_per_frame;
+    if keys.just_pressed(KeyCode::Up) {
+        playback.steps_per_frame = (steps_per_frame * 2).min(MAX_STEPS_PER_FRAME);
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        playback.steps_per_frame = (steps_per_frame / 2).max(1);
+    }
+    if playback.steps_per_frame != steps_per_frame {
+        println!("{} steps per frame", playback.steps_per_frame);
+    }
+    if keys.just_pressed(KeyCode::G) {
+        overlay.pixel_grid = !overlay.pixel_grid;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use clap::Parser;
+    use image::{Rgba, RgbaImage};
+
+    use ico_generator::config_constants::DELTILLES_PATH;
+    use ico_generator::export::{load_tile_images, render_net, TileImages};
+    use ico_generator::wave_function_collapse::StepOutcome;
+    use ico_generator::{Tileset, WfcConfig, WfcState};
+
+    use crate::cli::{Cli, Command};
+
+    use super::{render_progress, Playback, CHOSEN_TINT};
+
+    #[test]
+    fn paused_playback_only_takes_queued_steps() {
+        let mut playback = Playback {
+            steps_per_frame: 8,
+            ..Playback::default()
+        };
+        assert_eq!(playback.take_steps(), 8);
+        playback.paused = true;
+        assert_eq!(playback.take_steps(), 0);
+        playback.queued_steps = 1;
+        assert_eq!(playback.take_steps(), 1);
+        assert_eq!(playback.take_steps(), 0);
+    }
+
+    #[test]
+    fn parses_playback() {
+        let cli = Cli::parse_from([
+            "ico_generator",
+            "view",
+            "--paused",
+            "--steps-per-frame",
+            "16",
+        ]);
+        let Some(Command::View { playback, .. }) = cli.command else {
+            panic!("expected view, got {:?}", cli.command);
+        };
+        let playback = playback.playback();
+        assert!(playback.paused);
+        assert_eq!(playback.steps_per_frame, 16);
+    }
+
+    fn slot_centre(state: &WfcState, net: &RgbaImage, slot: usize) -> Rgba<u8> {
+        let position = state.icosahedron.deltille_slots[slot].position;
+        return *net.get_pixel(position.x as u32, net.height() - position.y as u32);
+    }
+
+    #[test]
+    fn progress_shades_undecided_slots_and_tints_the_choice() {
+        let deltille_options = Tileset::load(DELTILLES_PATH).unwrap().deltille_options();
+        let config = WfcConfig {
+            frequency: 3,
+            ..WfcConfig::default()
+        };
+        let mut state = WfcState::new(deltille_options, config);
+        let image_dir = Path::new(DELTILLES_PATH).parent().unwrap();
+        let tile_images = load_tile_images(&state, image_dir).unwrap();
+        let net = render_progress(&state, &tile_images);
+        for slot in 0..state.domains.len() {
+            let Rgba([r, g, b, a]) = slot_centre(&state, &net, slot);
+            assert!(r == g && g == b && a == 255);
+        }
+
+        state.step();
+        let chosen = state.last_choice.unwrap();
+        let plain = slot_centre(&state, &render_net(&state, &tile_images), chosen);
+        let tinted = slot_centre(&state, &render_progress(&state, &tile_images), chosen);
+        assert_ne!(plain, tinted);
+        for channel in 0..3 {
+            let (low, high) = if plain[channel] < CHOSEN_TINT[channel] {
+                (plain[channel], CHOSEN_TINT[channel])
+            } else {
+                (CHOSEN_TINT[channel], plain[channel])
+            };
+            assert!((low..=high).contains(&tinted[channel]));
+        }
+    }
+
+    #[test]
+    fn backtracks_tint_the_undone_choice() {
+        // neighbors differ in color, and the rare colors run out, so the solver has to
+        // unwind past its latest choice to an earlier one
+        let tile = |color: &str, weight: f32| {
+            return format!(
+                r#"{{ "image_path": "{color}.png", "weight": {weight},
+                     "sockets": {{ "Up": {{ "NE": "{color}", "S": "{color}", "NW": "{color}" }} }} }}"#
+            );
+        };
+        let tileset = Tileset::from_json(&format!(
+            r#"{{
+                "version": 2,
+                "socket_rules": {{
+                    "reversed": {{ "a": "a~", "b": "b~", "c": "c~" }},
+                    "pairs": [["a", "b"], ["a", "c"], ["b", "c"]]
+                }},
+                "deltilles": [{}, {}, {}]
+            }}"#,
+            tile("a", 0.1),
+            tile("b", 1.0),
+            tile("c", 0.1),
+        ))
+        .unwrap();
+        let config = WfcConfig {
+            frequency: 1,
+            ..WfcConfig::default()
+        };
+        let mut state = WfcState::new(tileset.deltille_options(), config);
+        let undone = loop {
+            match state.step() {
+                StepOutcome::Backtracked(slot_id) => break state.icosahedron.slot_index(&slot_id),
+                StepOutcome::Collapsed(_) => {}
+                outcome => panic!("expected a backtrack, got {outcome:?}"),
+            }
+        };
+        assert_eq!(state.last_choice, Some(undone));
+        // the step's own choice changed first, then the restored slots in index order
+        assert_ne!(state.changed_slots[0], undone);
+
+        let net = render_progress(&state, &TileImages::new());
+        // the choice's yellow is mostly red and green, the wave's blue mostly blue
+        let Rgba([red, _, blue, _]) = slot_centre(&state, &net, undone);
+        assert!(red > blue);
+        let Rgba([red, _, blue, _]) = slot_centre(&state, &net, state.changed_slots[0]);
+        assert!(blue > red);
+    }
+}
@@ -0,0 +1,129 @@
+//! 3D view of the icosahedron wrapped in the net texture, with an orbit camera: drag
+//! with the left mouse button to turn it, scroll to zoom.
+
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+use ico_generator::mesh::{MeshDetail, NetMesh};
+use ico_generator::wave_function_collapse::WfcState;
+
+use crate::playback::NetTexture;
+
+/// radians turned per pixel dragged
+const ORBIT_SPEED: f32 = 0.005;
+const MIN_DISTANCE: f32 = 1.5;
+const MAX_DISTANCE: f32 = 10.0;
+
+#[derive(Resource)]
+pub struct PreviewSettings {
+    pub detail: MeshDetail,
+}
+
+#[derive(Component)]
+pub struct OrbitCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl OrbitCamera {
+    fn transform(&self) -> Transform {
+        let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+        return Transform::from_translation(rotation * Vec3::Z * self.distance)
+            .looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
+
+pub fn setup_preview(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<PreviewSettings>,
+    texture: Res<NetTexture>,
+    state: Res<WfcState>,
+) {
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(bevy_mesh(&NetMesh::new(
+            &state.icosahedron,
+            settings.detail,
+        ))),
+        material: materials.add(StandardMaterial {
+            base_color_texture: Some(texture.0.clone()),
+            unlit: true,
+            ..default()
+        }),
+        ..default()
+    });
+
+    let orbit = OrbitCamera {
+        yaw: 0.0,
+        pitch: 0.0,
+        distance: 3.0,
+    };
+    commands.spawn((
+        Camera3dBundle {
+            transform: orbit.transform(),
+            ..default()
+        },
+        orbit,
+    ));
+}
+
+pub fn orbit_camera(
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut cameras: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let mut drag = Vec2::ZERO;
+    for motion in mouse_motion.iter() {
+        drag += motion.delta;
+    }
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        drag = Vec2::ZERO;
+    }
+    let scroll: f32 = mouse_wheel.iter().map(|wheel| wheel.y).sum();
+    if drag == Vec2::ZERO && scroll == 0.0 {
+        return;
+    }
+    for (mut orbit, mut transform) in cameras.iter_mut() {
+        orbit.yaw -= drag.x * ORBIT_SPEED;
+        // stop short of the poles so "up" stays defined
+        orbit.pitch = (orbit.pitch - drag.y * ORBIT_SPEED).clamp(-1.5, 1.5);
+        orbit.distance = (orbit.distance * 0.9_f32.powf(scroll)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+        *transform = orbit.transform();
+    }
+}
+
+fn bevy_mesh(net_mesh: &NetMesh) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        net_mesh
+            .positions
+            .iter()
+            .map(|position| position.to_array())
+            .collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        net_mesh
+            .normals
+            .iter()
+            .map(|normal| normal.to_array())
+            .collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        net_mesh
+            .uvs
+            .iter()
+            .map(|uv| uv.to_array())
+            .collect::<Vec<_>>(),
+    );
+    mesh.set_indices(Some(Indices::U32(
+        (0..net_mesh.positions.len() as u32).collect(),
+    )));
+    return mesh;
+}
@@ -0,0 +1,333 @@
+//! Resamples the rendered net into an equirectangular (latitude/longitude) image or the
+//! six faces of a cubemap.
+//!
+//! Every output pixel follows its direction to a deltille slot with
+//! `Icosahedron::slot_at` and reads the net image at the matching point of the slot.
+//! Points are kept a pixel inside their slot, so nearest sampling never reads across
+//! the net's seams; bilinear sampling skips transparent net pixels for the same reason.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::{Vec2, Vec3};
+use image::{Rgba, RgbaImage};
+
+use crate::config_constants::*;
+use crate::export::{load_tile_images, render_net, ExportError};
+use crate::icosahedron::{lat_long_direction, Icosahedron};
+use crate::wave_function_collapse::WfcState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sampling {
+    /// the nearest net pixel, which keeps pixel art crisp
+    #[default]
+    Nearest,
+
+    /// blends the four nearest net pixels
+    Bilinear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// `2 × size` by `size` pixels, longitude -π to π from left to right and the north
+    /// pole along the top
+    Equirectangular,
+
+    /// six `size` × `size` faces, written as `<name>_px.png`, `<name>_nx.png` and so on
+    Cubemap,
+}
+
+/// Cubemap faces in the usual order, +x, -x, +y, -y, +z, -z
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+pub const CUBE_FACES: [CubeFace; 6] = [
+    CubeFace::PositiveX,
+    CubeFace::NegativeX,
+    CubeFace::PositiveY,
+    CubeFace::NegativeY,
+    CubeFace::PositiveZ,
+    CubeFace::NegativeZ,
+];
+
+impl CubeFace {
+    /// Appended to the file name of the face's image
+    pub fn suffix(&self) -> &'static str {
+        return match self {
+            CubeFace::PositiveX => "px",
+            CubeFace::NegativeX => "nx",
+            CubeFace::PositiveY => "py",
+            CubeFace::NegativeY => "ny",
+            CubeFace::PositiveZ => "pz",
+            CubeFace::NegativeZ => "nz",
+        };
+    }
+
+    /// The direction through a point of the face, with `u` from -1 at the left edge to 1
+    /// at the right and `v` from -1 at the top to 1 at the bottom, laid out as OpenGL
+    /// and most engines expect
+    pub fn direction(&self, u: f32, v: f32) -> Vec3 {
+        return match self {
+            CubeFace::PositiveX => Vec3::new(1.0, -v, -u),
+            CubeFace::NegativeX => Vec3::new(-1.0, -v, u),
+            CubeFace::PositiveY => Vec3::new(u, 1.0, v),
+            CubeFace::NegativeY => Vec3::new(u, -1.0, -v),
+            CubeFace::PositiveZ => Vec3::new(u, -v, 1.0),
+            CubeFace::NegativeZ => Vec3::new(-u, -v, -1.0),
+        };
+    }
+}
+
+/// Resamples the net into a `width` × `height` latitude/longitude image
+pub fn equirectangular(
+    icosahedron: &Icosahedron,
+    net: &RgbaImage,
+    width: u32,
+    height: u32,
+    sampling: Sampling,
+) -> RgbaImage {
+    return RgbaImage::from_fn(width, height, |x, y| {
+        let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * std::f32::consts::TAU;
+        let latitude = (0.5 - (y as f32 + 0.5) / height as f32) * std::f32::consts::PI;
+        return sample(
+            icosahedron,
+            net,
+            lat_long_direction(latitude, longitude),
+            sampling,
+        );
+    });
+}
+
+/// Resamples the net into one `size` × `size` cubemap face
+pub fn cubemap_face(
+    icosahedron: &Icosahedron,
+    net: &RgbaImage,
+    face: CubeFace,
+    size: u32,
+    sampling: Sampling,
+) -> RgbaImage {
+    let to_face = |pixel: u32| (pixel as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    return RgbaImage::from_fn(size, size, |x, y| {
+        return sample(
+            icosahedron,
+            net,
+            face.direction(to_face(x), to_face(y)),
+            sampling,
+        );
+    });
+}
+
+/// All six cubemap faces, in the order of `CUBE_FACES`
+pub fn cubemap(
+    icosahedron: &Icosahedron,
+    net: &RgbaImage,
+    size: u32,
+    sampling: Sampling,
+) -> [RgbaImage; 6] {
+    return CUBE_FACES.map(|face| cubemap_face(icosahedron, net, face, size, sampling));
+}
+
+/// The net's colour in a direction from the center
+pub fn sample(
+    icosahedron: &Icosahedron,
+    net: &RgbaImage,
+    direction: Vec3,
+    sampling: Sampling,
+) -> Rgba<u8> {
+    let Some(point) = net_point(icosahedron, direction) else {
+        return Rgba([0, 0, 0, 0]);
+    };
+    // the net's y axis points up and the image's points down
+    let point = Vec2::new(point.x, net.height() as f32 - point.y);
+    return match sampling {
+        Sampling::Nearest => pixel(net, point.x.floor() as i64, point.y.floor() as i64),
+        Sampling::Bilinear => bilinear(net, point),
+    };
+}
+
+/// Where a direction lands in the net, in grid pixels with y up, kept a pixel inside
+/// its deltille slot
+pub fn net_point(icosahedron: &Icosahedron, direction: Vec3) -> Option<Vec2> {
+    let location = icosahedron.slot_at(direction)?;
+    let net_corners = icosahedron.deltille_net_corners(&location.slot_id);
+    // a barycentric coordinate times the slot's height is roughly the distance to the
+    // opposite edge
+    let margin = 1.0 / DELTILLE_GRID_HEIGHT as f32;
+    let weights = location.barycentric.map(|weight| weight.max(margin));
+    let total: f32 = weights.iter().sum();
+    return Some((0..3).fold(Vec2::ZERO, |point, corner| {
+        point + net_corners[corner] * weights[corner] / total
+    }));
+}
+
+/// Loads the tile images, renders the net and writes its projection to `path`; the
+/// cubemap faces go next to it with their suffixes
+pub fn export_projection(
+    state: &WfcState,
+    image_dir: &Path,
+    path: &Path,
+    projection: Projection,
+    size: u32,
+    sampling: Sampling,
+) -> Result<(), ExportError> {
+    let net = render_net(state, &load_tile_images(state, image_dir)?);
+    let save = |image: RgbaImage, path: PathBuf| {
+        return image
+            .save(&path)
+            .map_err(|error| ExportError::Save { path, error });
+    };
+    return match projection {
+        Projection::Equirectangular => save(
+            equirectangular(&state.icosahedron, &net, 2 * size, size, sampling),
+            path.to_path_buf(),
+        ),
+        Projection::Cubemap => {
+            for (face, image) in
+                CUBE_FACES
+                    .into_iter()
+                    .zip(cubemap(&state.icosahedron, &net, size, sampling))
+            {
+                save(image, cube_face_path(path, face))?;
+            }
+            Ok(())
+        }
+    };
+}
+
+/// `planet.png` becomes `planet_px.png` for the +x face
+pub fn cube_face_path(path: &Path, face: CubeFace) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or("png".as_ref()).to_string_lossy();
+    return path.with_file_name(format!("{stem}_{}.{extension}", face.suffix()));
+}
+
+/// The net pixel at `x`, `y`, transparent outside the image
+fn pixel(net: &RgbaImage, x: i64, y: i64) -> Rgba<u8> {
+    if x < 0 || y < 0 || x >= net.width() as i64 || y >= net.height() as i64 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    return *net.get_pixel(x as u32, y as u32);
+}
+
+/// Blends the four pixels around `point`, leaving out transparent ones so colours do
+/// not bleed in from outside the slot's icoface
+fn bilinear(net: &RgbaImage, point: Vec2) -> Rgba<u8> {
+    // pixel centers sit at half pixels
+    let point = point - Vec2::splat(0.5);
+    let left = point.x.floor();
+    let top = point.y.floor();
+    let fraction = point - Vec2::new(left, top);
+    let mut colour = [0.0_f32; 4];
+    let mut total_weight = 0.0;
+    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let texel = pixel(net, left as i64 + dx, top as i64 + dy);
+        if texel[3] == 0 {
+            continue;
+        }
+        let weight_x = if dx == 0 {
+            1.0 - fraction.x
+        } else {
+            fraction.x
+        };
+        let weight_y = if dy == 0 {
+            1.0 - fraction.y
+        } else {
+            fraction.y
+        };
+        let weight = weight_x * weight_y;
+        for (channel, value) in colour.iter_mut().zip(texel.0) {
+            *channel += value as f32 * weight;
+        }
+        total_weight += weight;
+    }
+    if total_weight == 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    return Rgba(colour.map(|channel| (channel / total_weight).round() as u8));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::prelude::{Vec2, Vec3};
+    use image::{Rgba, RgbaImage};
+
+    use crate::icosahedron::Icosahedron;
+
+    use super::{cube_face_path, cubemap, equirectangular, CubeFace, Sampling, CUBE_FACES};
+
+    /// A net with each slot filled with a colour made from its id
+    fn coloured_net(icosahedron: &Icosahedron) -> RgbaImage {
+        let mut net = RgbaImage::new(
+            icosahedron.net_grid_width() as u32,
+            icosahedron.net_grid_height() as u32,
+        );
+        for (x, y, pixel) in net.enumerate_pixels_mut() {
+            let point = Vec2::new(
+                x as f32 + 0.5,
+                icosahedron.net_grid_height() as f32 - (y as f32 + 0.5),
+            );
+            for slot_id in icosahedron.deltille_slot_ids() {
+                let [a, b, c] = icosahedron.deltille_net_corners(&slot_id);
+                let inside = [(a, b), (b, c), (c, a)]
+                    .iter()
+                    .all(|(from, to)| (*to - *from).perp_dot(point - *from) >= 0.0);
+                if inside {
+                    *pixel = Rgba([slot_id.icoface_id as u8, slot_id.deltille_id as u8, 0, 255]);
+                }
+            }
+        }
+        return net;
+    }
+
+    #[test]
+    fn projections_cover_the_whole_sphere_with_the_right_slots() {
+        let icosahedron = Icosahedron::new(2);
+        let net = coloured_net(&icosahedron);
+        let image = equirectangular(&icosahedron, &net, 64, 32, Sampling::Nearest);
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let longitude = ((x as f32 + 0.5) / 64.0 - 0.5) * std::f32::consts::TAU;
+            let latitude = (0.5 - (y as f32 + 0.5) / 32.0) * std::f32::consts::PI;
+            let slot_id = icosahedron.slot_at_lat_long(latitude, longitude).slot_id;
+            assert_eq!(
+                *pixel,
+                Rgba([slot_id.icoface_id as u8, slot_id.deltille_id as u8, 0, 255]),
+                "pixel {x}, {y}"
+            );
+        }
+
+        for face in cubemap(&icosahedron, &net, 16, Sampling::Bilinear) {
+            assert!(face.pixels().all(|pixel| pixel[3] == 255));
+        }
+    }
+
+    #[test]
+    fn cube_faces_point_along_their_axis() {
+        let axes = [
+            Vec3::X,
+            Vec3::NEG_X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::Z,
+            Vec3::NEG_Z,
+        ];
+        for (face, axis) in CUBE_FACES.into_iter().zip(axes) {
+            assert_eq!(face.direction(0.0, 0.0), axis);
+            // every face is right-handed seen from inside the cube
+            let right = face.direction(1.0, 0.0) - axis;
+            let down = face.direction(0.0, 1.0) - axis;
+            assert_eq!(right.cross(down), -axis, "{face:?}");
+        }
+        assert_eq!(
+            cube_face_path(Path::new("out/sky.png"), CubeFace::NegativeY),
+            Path::new("out/sky_ny.png")
+        );
+    }
+}
@@ -0,0 +1,725 @@
+//! Tileset files describe the deltilles the solver may place.
+//!
+//! ```json
+//! {
+//!     "version": 2,
+//!     "socket_rules": {
+//!         "reversed": { "land-ocean": "ocean-land" },
+//!         "pairs": [["cliff", "beach"]],
+//!         "wildcards": ["*"]
+//!     },
+//!     "deltilles": [
+//!         {
+//!             "image_path": "land_full.png",
+//!             "weight": 1.0,
+//!             "sockets": {
+//!                 "Up": { "NE": "land", "S": "land", "NW": "land" }
+//!             }
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Images are drawn as "up" deltilles. `sockets` gives the socket label on each edge of
+//! the image as drawn (`NE`, `S` and `NW` under `Up`) or of the image flipped upside down
+//! (`N`, `SE` and `SW` under `Down`). One of the two is enough: every deltille is
+//! expanded into all of its rotations and mirror images in both orientations, with
+//! variants that have the same sockets as an earlier one dropped. Give both only when
+//! the flipped image needs different sockets than flipping would produce.
+//!
+//! Variants are told apart by their sockets alone, since those are all the solver sees:
+//! a rotation or mirror image whose sockets match an earlier variant's is dropped even
+//! if its image looks different. To keep such an image in play, list it again as its
+//! own deltille.
+//!
+//! `weight` is optional and defaults to 1. It is shared evenly between the variants a
+//! deltille keeps, so symmetric deltilles are not less likely than asymmetric ones.
+//!
+//! Socket labels are read clockwise around their own deltille, so two deltilles that
+//! share an edge read it in opposite directions. A label matches the reverse of itself:
+//! labels listed in `socket_rules.reversed` (in either direction) are directional edge
+//! profiles, and every other label reads the same both ways, so it simply matches
+//! itself. `pairs` lists further labels that may meet, and `wildcards` match anything.
+//! Mirror image variants read their edges backwards too, so their directional labels
+//! are reversed. `socket_rules` is optional.
+//!
+//! Version 0 files are a bare array of deltilles with lowercase socket keys, and version
+//! 1 files have no `socket_rules`; both still load, and `Tileset::save` writes them back
+//! out as the current version.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config_constants::MAX_DELTILLE_OPTIONS;
+use crate::domain::Domain;
+use crate::icosahedron::{
+    ArrayIndex, Deltille, DeltilleFaceSocket, VerticalOrientation, DELTILLE_FACE_SOCKET_COUNT,
+    SOCKET_COUNT, VERTICAL_ORIENTATION_COUNT,
+};
+
+pub const TILESET_VERSION: u32 = 2;
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeltilleDefinition {
+    pub image_path: String,
+
+    /// relative likelihood of being chosen when a slot collapses
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    pub sockets: BTreeMap<VerticalOrientation, BTreeMap<DeltilleFaceSocket, String>>,
+}
+
+/// Which socket labels may meet across a shared edge
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SocketRules {
+    /// directional labels and the same edge profile read the other way
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reversed: BTreeMap<String, String>,
+
+    /// label pairs that may meet in either order, besides a label and its reverse
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pairs: Vec<[String; 2]>,
+
+    /// labels that may meet any label
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wildcards: Vec<String>,
+}
+
+impl SocketRules {
+    /// The label as read from the other end of its edge
+    pub fn reverse<'a>(&'a self, label: &'a str) -> &'a str {
+        if let Some(reversed) = self.reversed.get(label) {
+            return reversed;
+        }
+        return self
+            .reversed
+            .iter()
+            .find(|(_, reversed)| *reversed == label)
+            .map_or(label, |(forward, _)| forward);
+    }
+
+    /// Whether a deltille with `label` on an edge may sit next to one with `other_label`
+    /// on the same edge
+    pub fn matches(&self, label: &str, other_label: &str) -> bool {
+        return self.reverse(label) == other_label
+            || self
+                .wildcards
+                .iter()
+                .any(|wildcard| wildcard == label || wildcard == other_label)
+            || self.pairs.iter().any(|[a, b]| {
+                (a == label && b == other_label) || (a == other_label && b == label)
+            });
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut seen: Vec<&String> = Vec::new();
+        for label in self
+            .reversed
+            .iter()
+            .flat_map(|(forward, reversed)| [forward, reversed])
+        {
+            if seen.contains(&label) {
+                return Err(format!("\"{label}\" is reversed more than once"));
+            }
+            seen.push(label);
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tileset {
+    pub version: u32,
+    pub socket_rules: SocketRules,
+    pub deltilles: Vec<DeltilleDefinition>,
+}
+
+/// Everything the solver needs from a tileset
+#[derive(Debug, Clone, Default)]
+pub struct DeltilleOptions {
+    /// deltilles available to "up" and "down" deltille slots, indexed by
+    /// `VerticalOrientation::index()`; slot option ids index into these
+    pub deltilles: [Vec<Deltille>; VERTICAL_ORIENTATION_COUNT],
+    pub socket_rules: SocketRules,
+
+    /// compiled from the two above by `new`, which has to be called again if they change
+    pub supports: Supports,
+}
+
+impl DeltilleOptions {
+    pub fn new(
+        deltilles: [Vec<Deltille>; VERTICAL_ORIENTATION_COUNT],
+        socket_rules: SocketRules,
+    ) -> Self {
+        let supports = Supports::new(&deltilles, &socket_rules);
+        return DeltilleOptions {
+            deltilles,
+            socket_rules,
+            supports,
+        };
+    }
+}
+
+/// Which deltilles may sit next to which, worked out once from their sockets so the
+/// solver never compares labels
+#[derive(Debug, Clone, Default)]
+pub struct Supports {
+    /// `allowed[socket][target_socket][id]`, with sockets as `DeltilleFaceSocket`
+    /// discriminants
+    allowed: Vec<Vec<Vec<Domain>>>,
+}
+
+impl Supports {
+    pub fn new(
+        deltilles: &[Vec<Deltille>; VERTICAL_ORIENTATION_COUNT],
+        socket_rules: &SocketRules,
+    ) -> Self {
+        let mut allowed =
+            vec![vec![Vec::new(); DELTILLE_FACE_SOCKET_COUNT]; DELTILLE_FACE_SOCKET_COUNT];
+        for orientation in [VerticalOrientation::Up, VerticalOrientation::Down] {
+            for target_orientation in [VerticalOrientation::Up, VerticalOrientation::Down] {
+                let targets = &deltilles[target_orientation.index()];
+                for (index, socket) in orientation.sockets().into_iter().enumerate() {
+                    for (target_index, target_socket) in
+                        target_orientation.sockets().into_iter().enumerate()
+                    {
+                        allowed[socket as usize][target_socket as usize] = deltilles
+                            [orientation.index()]
+                        .iter()
+                        .map(|deltille| {
+                            let label = &deltille.sockets[index];
+                            return targets
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, target)| {
+                                    socket_rules.matches(label, &target.sockets[target_index])
+                                })
+                                .map(|(target_id, _)| target_id)
+                                .collect();
+                        })
+                        .collect();
+                    }
+                }
+            }
+        }
+        return Supports { allowed };
+    }
+
+    /// The deltilles that may face deltille `id` across its `socket`, as ids among the
+    /// deltilles of the neighbor, which meets it with `target_socket`
+    pub fn allowed(
+        &self,
+        socket: DeltilleFaceSocket,
+        target_socket: DeltilleFaceSocket,
+        id: usize,
+    ) -> &Domain {
+        return &self.allowed[socket as usize][target_socket as usize][id];
+    }
+}
+
+#[derive(Debug)]
+pub enum TilesetError {
+    Io(io::Error),
+
+    /// the file is not valid JSON, or not an object or array at the top level
+    Json(serde_json::Error),
+    UnsupportedVersion(u64),
+    SocketRules(String),
+
+    /// the deltilles have more variants of one orientation than a `Domain` can hold
+    TooManyVariants {
+        orientation: VerticalOrientation,
+        count: usize,
+    },
+
+    /// deltille `index` could not be read
+    Entry {
+        index: usize,
+        image_path: Option<String>,
+        message: String,
+    },
+}
+
+impl fmt::Display for TilesetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilesetError::Io(error) => write!(f, "{error}"),
+            TilesetError::Json(error) => write!(f, "invalid tileset JSON: {error}"),
+            TilesetError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported tileset version {version} (newest supported is {TILESET_VERSION})"
+            ),
+            TilesetError::SocketRules(message) => write!(f, "socket_rules: {message}"),
+            TilesetError::TooManyVariants { orientation, count } => write!(
+                f,
+                "{count} {} variants, at most {MAX_DELTILLE_OPTIONS} are supported",
+                match orientation {
+                    VerticalOrientation::Up => "up",
+                    VerticalOrientation::Down => "down",
+                }
+            ),
+            TilesetError::Entry {
+                index,
+                image_path: Some(image_path),
+                message,
+            } => write!(f, "deltille {index} ({image_path}): {message}"),
+            TilesetError::Entry {
+                index,
+                image_path: None,
+                message,
+            } => write!(f, "deltille {index}: {message}"),
+        }
+    }
+}
+
+/// Up sockets after rotating the image a third of a turn clockwise: NE moves to S,
+/// S to NW and NW to NE
+fn rotated([ne, s, nw]: &[String; SOCKET_COUNT]) -> [String; SOCKET_COUNT] {
+    return [nw.clone(), ne.clone(), s.clone()];
+}
+
+/// Up sockets after mirroring the image left to right
+fn mirrored([ne, s, nw]: &[String; SOCKET_COUNT]) -> [String; SOCKET_COUNT] {
+    return [nw.clone(), s.clone(), ne.clone()];
+}
+
+/// Down sockets after flipping an up image upside down: S moves to N, NE to SE and
+/// NW to SW
+fn flipped_to_down([ne, s, nw]: &[String; SOCKET_COUNT]) -> [String; SOCKET_COUNT] {
+    return [s.clone(), ne.clone(), nw.clone()];
+}
+
+/// Undoes `flipped_to_down`
+fn flipped_to_up([n, se, sw]: &[String; SOCKET_COUNT]) -> [String; SOCKET_COUNT] {
+    return [se.clone(), n.clone(), sw.clone()];
+}
+
+impl DeltilleDefinition {
+    /// Sockets for each orientation in `ArrayIndex` order, as given in the definition
+    fn sockets_by_orientation(
+        &self,
+    ) -> Result<[Option<[String; SOCKET_COUNT]>; VERTICAL_ORIENTATION_COUNT], String> {
+        let mut sockets_by_orientation = [None, None];
+        for (orientation, sockets) in self.sockets.iter() {
+            let valid_sockets = orientation.sockets();
+            if let Some(invalid) = sockets
+                .keys()
+                .find(|socket| !valid_sockets.contains(socket))
+            {
+                return Err(format!(
+                    "{invalid:?} is not a socket of an {orientation:?} deltille, expected {valid_sockets:?}"
+                ));
+            }
+            let mut labels: [String; SOCKET_COUNT] = Default::default();
+            for socket in valid_sockets {
+                labels[socket.index()] = sockets
+                    .get(&socket)
+                    .ok_or_else(|| format!("missing {socket:?} socket for {orientation:?}"))?
+                    .clone();
+            }
+            sockets_by_orientation[orientation.index()] = Some(labels);
+        }
+        if sockets_by_orientation.iter().all(Option::is_none) {
+            return Err("no sockets for Up or Down".to_string());
+        }
+        return Ok(sockets_by_orientation);
+    }
+
+    /// Every distinct rotation and mirror image of this deltille in each orientation
+    fn variants(&self, socket_rules: &SocketRules) -> [Vec<Deltille>; VERTICAL_ORIENTATION_COUNT] {
+        // definitions are checked when the tileset is loaded
+        let [up, down] = self.sockets_by_orientation().unwrap();
+        // up sockets of the image before it is flipped into each orientation; flipping
+        // reads every edge the other way, so undoing it reverses the labels again
+        let unflipped = |down: &[String; SOCKET_COUNT]| {
+            flipped_to_up(down).map(|label| socket_rules.reverse(&label).to_string())
+        };
+        let up_for_up = up.clone().or_else(|| down.as_ref().map(unflipped)).unwrap();
+        let up_for_down = down.as_ref().map(unflipped).or(up).unwrap();
+
+        let mut variants: [Vec<Deltille>; VERTICAL_ORIENTATION_COUNT] = Default::default();
+        for orientation in [VerticalOrientation::Up, VerticalOrientation::Down] {
+            let flip_y = orientation == VerticalOrientation::Down;
+            let base = if flip_y { &up_for_down } else { &up_for_up };
+            let orientation_variants = &mut variants[orientation.index()];
+            let mut rotated_sockets = base.clone();
+            for rotation in 0..3 {
+                for flip_x in [false, true] {
+                    let mut sockets = if flip_x {
+                        mirrored(&rotated_sockets)
+                    } else {
+                        rotated_sockets.clone()
+                    };
+                    if flip_y {
+                        sockets = flipped_to_down(&sockets);
+                    }
+                    if flip_x != flip_y {
+                        // a mirror image reads each edge the other way
+                        sockets = sockets.map(|label| socket_rules.reverse(&label).to_string());
+                    }
+                    if orientation_variants
+                        .iter()
+                        .any(|variant| variant.sockets == sockets)
+                    {
+                        continue;
+                    }
+                    orientation_variants.push(Deltille {
+                        image_path: self.image_path.clone(),
+                        rotation,
+                        flip_x,
+                        flip_y,
+                        weight: self.weight,
+                        sockets,
+                    });
+                }
+                rotated_sockets = rotated(&rotated_sockets);
+            }
+            let variant_count = orientation_variants.len() as f32;
+            for variant in orientation_variants.iter_mut() {
+                variant.weight /= variant_count;
+            }
+        }
+        return variants;
+    }
+}
+
+impl Tileset {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TilesetError> {
+        let json = fs::read_to_string(path).map_err(TilesetError::Io)?;
+        return Self::from_json(&json);
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TilesetError> {
+        let mut json = serde_json::to_string_pretty(self).map_err(TilesetError::Json)?;
+        json.push('\n');
+        return fs::write(path, json).map_err(TilesetError::Io);
+    }
+
+    /// Parses a tileset of any supported version, reporting problems per deltille
+    pub fn from_json(json: &str) -> Result<Self, TilesetError> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(TilesetError::Json)?;
+        let mut socket_rules = SocketRules::default();
+        let entries = match value {
+            // version 0: a bare array of deltilles
+            serde_json::Value::Array(entries) => entries,
+            serde_json::Value::Object(mut object) => {
+                let version = object
+                    .get("version")
+                    .and_then(serde_json::Value::as_u64)
+                    .unwrap_or(0);
+                if version > TILESET_VERSION as u64 {
+                    return Err(TilesetError::UnsupportedVersion(version));
+                }
+                if let Some(rules) = object.remove("socket_rules") {
+                    socket_rules = serde_json::from_value(rules)
+                        .map_err(|error| TilesetError::SocketRules(error.to_string()))?;
+                    socket_rules.validate().map_err(TilesetError::SocketRules)?;
+                }
+                match object.remove("deltilles") {
+                    Some(serde_json::Value::Array(entries)) => entries,
+                    _ => {
+                        return Err(TilesetError::Json(serde::de::Error::custom(
+                            "expected a \"deltilles\" array",
+                        )))
+                    }
+                }
+            }
+            _ => {
+                return Err(TilesetError::Json(serde::de::Error::custom(
+                    "expected an object or an array of deltilles",
+                )))
+            }
+        };
+
+        let mut deltilles = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.into_iter().enumerate() {
+            let image_path = entry
+                .get("image_path")
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string);
+            let entry_error = |message: String| TilesetError::Entry {
+                index,
+                image_path: image_path.clone(),
+                message,
+            };
+            let deltille: DeltilleDefinition =
+                serde_json::from_value(entry).map_err(|error| entry_error(error.to_string()))?;
+            if !(deltille.weight >= 0.0 && deltille.weight.is_finite()) {
+                return Err(entry_error(format!(
+                    "weight must be a finite number no less than 0, got {}",
+                    deltille.weight
+                )));
+            }
+            deltille.sockets_by_orientation().map_err(entry_error)?;
+            deltilles.push(deltille);
+        }
+        let tileset = Tileset {
+            version: TILESET_VERSION,
+            socket_rules,
+            deltilles,
+        };
+        let mut counts = [0; VERTICAL_ORIENTATION_COUNT];
+        for definition in tileset.deltilles.iter() {
+            let variants = definition.variants(&tileset.socket_rules);
+            for (count, variants) in counts.iter_mut().zip(variants) {
+                *count += variants.len();
+            }
+        }
+        for orientation in [VerticalOrientation::Up, VerticalOrientation::Down] {
+            let count = counts[orientation.index()];
+            if count > MAX_DELTILLE_OPTIONS {
+                return Err(TilesetError::TooManyVariants { orientation, count });
+            }
+        }
+        return Ok(tileset);
+    }
+
+    /// Every variant of each definition, ready for the solver
+    pub fn deltille_options(&self) -> DeltilleOptions {
+        let mut deltilles: [Vec<Deltille>; VERTICAL_ORIENTATION_COUNT] = Default::default();
+        for definition in self.deltilles.iter() {
+            let variants = definition.variants(&self.socket_rules);
+            for (orientation_deltilles, variants) in deltilles.iter_mut().zip(variants) {
+                orientation_deltilles.extend(variants);
+            }
+        }
+        return DeltilleOptions::new(deltilles, self.socket_rules.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use crate::config_constants::{DELTILLES_PATH, MAX_DELTILLE_OPTIONS};
+    use crate::icosahedron::{ArrayIndex, Deltille, VerticalOrientation};
+
+    use super::{SocketRules, Tileset, TilesetError, TILESET_VERSION};
+
+    #[test]
+    fn loads_version_0() {
+        let tileset = Tileset::from_json(
+            r#"[{ "image_path": "a.png", "sockets": { "Up": { "nw": "x", "ne": "y", "s": "z" } } }]"#,
+        )
+        .unwrap();
+        assert_eq!(tileset.version, TILESET_VERSION);
+
+        let options = tileset.deltille_options().deltilles;
+        let up = &options[VerticalOrientation::Up.index()][0];
+        assert_eq!(up.sockets, ["y", "z", "x"].map(String::from));
+        assert!(!up.flip_y);
+        let down = &options[VerticalOrientation::Down.index()][0];
+        assert_eq!(down.sockets, ["z", "y", "x"].map(String::from));
+        assert!(down.flip_y);
+    }
+
+    #[test]
+    fn round_trips_current_version() {
+        let json = r#"{
+            "version": 2,
+            "socket_rules": { "reversed": { "x": "x'" }, "wildcards": ["*"] },
+            "deltilles": [
+                { "image_path": "a.png", "weight": 2.0, "sockets": { "Down": { "N": "x", "SE": "y", "SW": "z" } } }
+            ]
+        }"#;
+        let tileset = Tileset::from_json(json).unwrap();
+        let saved = serde_json::to_string(&tileset).unwrap();
+        assert_eq!(Tileset::from_json(&saved).unwrap(), tileset);
+
+        let options = tileset.deltille_options().deltilles;
+        let down = &options[VerticalOrientation::Down.index()];
+        assert_eq!(down[0].sockets, ["x", "y", "z"].map(String::from));
+        let total_weight: f32 = down.iter().map(|deltille| deltille.weight).sum();
+        assert!((total_weight - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn variants_must_fit_in_a_domain() {
+        let entries: Vec<String> = (0..MAX_DELTILLE_OPTIONS)
+            .map(|index| {
+                format!(
+                    r#"{{ "image_path": "{index}.png", "sockets": {{ "Up": {{ "NE": "a{index}", "S": "b{index}", "NW": "c{index}" }} }} }}"#
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{ "version": 2, "deltilles": [{}] }}"#,
+            entries.join(",")
+        );
+        let error = Tileset::from_json(&json).unwrap_err();
+        assert!(
+            matches!(error, TilesetError::TooManyVariants { .. }),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn errors_name_the_bad_deltille() {
+        let json = r#"{ "version": 2, "deltilles": [
+            { "image_path": "a.png", "sockets": { "Up": { "NE": "x", "S": "y", "NW": "z" } } },
+            { "image_path": "b.png", "sockets": { "Up": { "NE": "x", "S": "y" } } }
+        ] }"#;
+        let error = Tileset::from_json(json).unwrap_err();
+        assert!(matches!(error, TilesetError::Entry { index: 1, .. }));
+        assert_eq!(
+            error.to_string(),
+            "deltille 1 (b.png): missing NW socket for Up"
+        );
+    }
+
+    #[test]
+    fn rejects_sockets_of_the_wrong_orientation() {
+        let json = r#"[{ "image_path": "a.png", "sockets": { "Up": { "N": "x", "S": "y", "NW": "z" } } }]"#;
+        let error = Tileset::from_json(json).unwrap_err();
+        assert!(matches!(error, TilesetError::Entry { index: 0, .. }));
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let error = Tileset::from_json(r#"{ "version": 99, "deltilles": [] }"#).unwrap_err();
+        assert!(matches!(error, TilesetError::UnsupportedVersion(99)));
+    }
+
+    #[test]
+    fn bundled_tileset_loads() {
+        let tileset = Tileset::load(DELTILLES_PATH).unwrap();
+        assert_eq!(tileset.deltilles.len(), 16);
+    }
+
+    fn options_for(up_sockets: [&str; 3]) -> [Vec<Deltille>; 2] {
+        let [ne, s, nw] = up_sockets;
+        let json = format!(
+            r#"[{{ "image_path": "a.png", "sockets": {{ "Up": {{ "NE": "{ne}", "S": "{s}", "NW": "{nw}" }} }} }}]"#
+        );
+        return Tileset::from_json(&json)
+            .unwrap()
+            .deltille_options()
+            .deltilles;
+    }
+
+    #[test]
+    fn variants_are_told_apart_by_sockets_alone() {
+        // each mirror image has the sockets of a rotation, so only three of the six
+        // transforms are kept, however different the other three look
+        let [up, down] = options_for(["a", "b", "b"]);
+        assert_eq!(up.len(), 3);
+        assert_eq!(down.len(), 3);
+        for (index, variant) in up.iter().enumerate() {
+            assert!(up[..index]
+                .iter()
+                .all(|other| other.sockets != variant.sockets));
+        }
+        let total_weight: f32 = up.iter().map(|variant| variant.weight).sum();
+        assert!((total_weight - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn symmetric_deltilles_have_one_variant() {
+        let [up, down] = options_for(["a", "a", "a"]);
+        assert_eq!(up.len(), 1);
+        assert_eq!(down.len(), 1);
+        assert_eq!(up[0].weight, 1.0);
+    }
+
+    #[test]
+    fn mirror_symmetric_deltilles_have_three_variants() {
+        let [up, down] = options_for(["a", "b", "a"]);
+        assert_eq!(up.len(), 3);
+        assert_eq!(down.len(), 3);
+    }
+
+    #[test]
+    fn asymmetric_deltilles_have_six_variants() {
+        let [up, down] = options_for(["x", "y", "z"]);
+        assert_eq!(up.len(), 6);
+        assert_eq!(down.len(), 6);
+        for variants in [&up, &down] {
+            let distinct: HashSet<&[String; 3]> = variants.iter().map(|v| &v.sockets).collect();
+            assert_eq!(distinct.len(), 6);
+            let total_weight: f32 = variants.iter().map(|v| v.weight).sum();
+            assert!((total_weight - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn variant_sockets_follow_their_transform() {
+        let [up, down] = options_for(["x", "y", "z"]);
+        let find = |variants: &[Deltille], rotation: usize, flip_x: bool| {
+            variants
+                .iter()
+                .find(|v| v.rotation == rotation && v.flip_x == flip_x)
+                .unwrap()
+                .sockets
+                .clone()
+        };
+        // a third of a turn clockwise moves NW to NE, NE to S and S to NW
+        assert_eq!(find(&up, 1, false), ["z", "x", "y"].map(String::from));
+        // mirroring swaps NE and NW
+        assert_eq!(find(&up, 0, true), ["z", "y", "x"].map(String::from));
+        // rotating then mirroring
+        assert_eq!(find(&up, 1, true), ["y", "x", "z"].map(String::from));
+        // flipping upside down moves S to N, NE to SE and NW to SW
+        assert_eq!(find(&down, 0, false), ["y", "x", "z"].map(String::from));
+        assert!(down.iter().all(|v| v.flip_y));
+        assert!(up.iter().all(|v| !v.flip_y));
+    }
+
+    #[test]
+    fn directional_labels_match_their_reverse() {
+        let rules = SocketRules {
+            reversed: BTreeMap::from([("land-ocean".to_string(), "ocean-land".to_string())]),
+            pairs: vec![["cliff".to_string(), "beach".to_string()]],
+            wildcards: vec!["*".to_string()],
+        };
+        assert!(rules.matches("land", "land"));
+        assert!(!rules.matches("land", "ocean"));
+        assert!(rules.matches("land-ocean", "ocean-land"));
+        assert!(rules.matches("ocean-land", "land-ocean"));
+        assert!(!rules.matches("land-ocean", "land-ocean"));
+        assert!(rules.matches("beach", "cliff"));
+        assert!(!rules.matches("cliff", "land"));
+        assert!(rules.matches("*", "ocean-land"));
+        assert!(rules.matches("land", "*"));
+    }
+
+    #[test]
+    fn mirror_images_reverse_directional_labels() {
+        let json = r#"{
+            "version": 2,
+            "socket_rules": { "reversed": { "lo": "ol" } },
+            "deltilles": [
+                { "image_path": "a.png", "sockets": { "Up": { "NE": "lo", "S": "o", "NW": "o" } } }
+            ]
+        }"#;
+        let [up, down] = Tileset::from_json(json)
+            .unwrap()
+            .deltille_options()
+            .deltilles;
+        let mirrored = up.iter().find(|v| v.rotation == 0 && v.flip_x).unwrap();
+        assert_eq!(mirrored.sockets, ["o", "o", "ol"].map(String::from));
+
+        let flipped = down.iter().find(|v| v.rotation == 0 && !v.flip_x).unwrap();
+        assert_eq!(flipped.sockets, ["o", "ol", "o"].map(String::from));
+        let flipped_and_mirrored = down.iter().find(|v| v.rotation == 0 && v.flip_x).unwrap();
+        assert_eq!(
+            flipped_and_mirrored.sockets,
+            ["o", "o", "lo"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn rejects_ambiguous_reversals() {
+        let json = r#"{ "version": 2, "socket_rules": { "reversed": { "a": "b", "b": "c" } }, "deltilles": [] }"#;
+        let error = Tileset::from_json(json).unwrap_err();
+        assert!(matches!(error, TilesetError::SocketRules(_)));
+    }
+}
@@ -0,0 +1,97 @@
+//! The `view` window: the net solving in 2D, or wrapped onto the icosphere in 3D.
+
+use bevy::prelude::*;
+use bevy::window::WindowResolution;
+use ico_generator::config_constants::*;
+use ico_generator::export::load_tile_images;
+use ico_generator::wave_function_collapse::*;
+
+use crate::cli::{MeshArg, PlaybackArgs, SolverArgs};
+use crate::graphics::*;
+use crate::playback::*;
+use crate::preview::*;
+
+pub fn view(
+    solver: &SolverArgs,
+    seed: u64,
+    three_d: bool,
+    mesh: MeshArg,
+    playback: &PlaybackArgs,
+) -> Result<(), String> {
+    let deltille_options = solver.deltille_options()?;
+    let config = solver.config(seed);
+    let state = WfcState::new(deltille_options, config);
+    let tile_images =
+        load_tile_images(&state, solver.image_dir()).map_err(|error| error.to_string())?;
+    let net_size = Vec2::new(
+        state.icosahedron.net_grid_width() as f32,
+        state.icosahedron.net_grid_height() as f32,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Icosphere Texture Generator".to_string(),
+                    resolution: WindowResolution::new(
+                        net_size.x * view_scale(config.frequency),
+                        net_size.y * view_scale(config.frequency),
+                    ),
+                    ..default()
+                }),
+                ..default()
+            })
+            .set(ImagePlugin::default_nearest()),
+    )
+    .insert_resource(config)
+    .insert_resource(playback.playback())
+    .insert_resource(NetTiles(tile_images))
+    .insert_resource(DebugOverlay::default())
+    .add_systems(
+        Update,
+        (
+            control_playback,
+            iterate_wfc.run_if(not_yet_complete),
+            update_net_texture,
+        )
+            .chain(),
+    );
+    if three_d {
+        app.insert_resource(PreviewSettings {
+            detail: mesh.into(),
+        })
+        .add_systems(Startup, (setup_net_texture, setup_preview).chain())
+        .add_systems(Update, orbit_camera);
+    } else {
+        app.add_systems(
+            Startup,
+            (setup_net_texture, setup_net_sprite, setup).chain(),
+        )
+        .add_systems(Update, draw_debug);
+    }
+    app.insert_resource(state).run();
+    return Ok(());
+}
+
+/// Pixels per grid unit, shrinking finer nets so the window stays the same size
+fn view_scale(frequency: usize) -> f32 {
+    return VIEW_SCALE * DEFAULT_FREQUENCY as f32 / frequency as f32;
+}
+
+fn setup(mut commands: Commands, mut gizmo_config: ResMut<GizmoConfig>, state: Res<WfcState>) {
+    let icosahedron = &state.icosahedron;
+    commands.spawn(Camera2dBundle {
+        projection: OrthographicProjection {
+            scale: 1.0 / view_scale(icosahedron.frequency),
+            ..default()
+        },
+        transform: Transform::from_xyz(
+            icosahedron.net_grid_width() as f32 / 2.0,
+            icosahedron.net_grid_height() as f32 / 2.0,
+            1.0,
+        ),
+        ..default()
+    });
+    gizmo_config.line_width = 1.5;
+}
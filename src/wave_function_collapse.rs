@@ -1,120 +1,810 @@
-// use std::{array::from_fn, fs};
-
-// use bevy::prelude::{Res, ResMut, Resource};
-// use rand::seq::SliceRandom;
-
-// use crate::icosahedron::{Deltille, DeltilleSlotId, IcoFace, Icosahedron, VerticalOrientation};
-
-// #[derive(Resource)]
-// pub struct WfcState {
-//     pub steps: Vec<Step>,
-//     pub complete: bool,
-// }
-
-// fn up_options_from(deltille_inputs: &Vec<Deltille>) -> Vec<Deltille> {
-//     let mut deltilles = Vec::with_capacity(deltille_inputs.len() * 2);
-//     for deltille in deltille_inputs.iter() {
-//         deltilles.push(deltille.clone());
-//         let mut flipped = deltille.clone();
-//         flipped.flip_x = true;
-//         deltilles.push(flipped);
-//     }
-//     return deltilles;
-// }
-
-// fn down_options_from(deltille_inputs: &Vec<Deltille>) -> Vec<Deltille> {
-//     let mut deltilles = Vec::with_capacity(deltille_inputs.len() * 2);
-//     for deltille in deltille_inputs.iter() {
-//         let mut clone = deltille.clone();
-//         clone.flip_y = true;
-//         deltilles.push(clone);
-//         let mut flipped = deltille.clone();
-//         flipped.flip_x = true;
-//         flipped.flip_y = true;
-//         deltilles.push(flipped);
-//     }
-//     return deltilles;
-// }
-
-// impl WfcState {
-//     pub fn new() -> Self {
-//         let deltille_json = fs::read_to_string("assets/deltilles.json").unwrap_or("[]".to_string());
-//         let deltilles: Vec<Deltille> = serde_json::from_str(&deltille_json).unwrap();
-
-//         let up_options = up_options_from(&deltilles);
-//         let down_options = down_options_from(&deltilles);
-
-//         let up_icoface =
-//             IcoFace::with_initial_options(VerticalOrientation::Up, &up_options, &down_options);
-//         let down_icoface =
-//             IcoFace::with_initial_options(VerticalOrientation::Down, &up_options, &down_options);
-
-//         let icosahedron = Icosahedron {
-//             faces: [
-//                 from_fn(|_| up_icoface.clone()),
-//                 from_fn(|_| down_icoface.clone()),
-//                 from_fn(|_| up_icoface.clone()),
-//                 from_fn(|_| down_icoface.clone()),
-//             ],
-//         };
-
-//         WfcState {
-//             steps: vec![Step { icosahedron }],
-//             complete: false,
-//         }
-//     }
-// }
-
-// pub struct Step {
-//     icosahedron: Icosahedron,
-// }
-
-// pub fn not_yet_complete(state: Res<WfcState>) -> bool {
-//     return !state.complete;
-// }
-
-// pub fn iterate_wfc(mut state: ResMut<WfcState>) {
-//     let mut icosahedron = &mut state.steps.last_mut().unwrap().icosahedron;
-//     let deltille_coordinate_options = coordinates_with_fewest_possibilities(&icosahedron);
-//     let deltille_coordinate_choice = deltille_coordinate_options.choose(&mut rand::thread_rng());
-//     match deltille_coordinate_choice {
-//         Some(chosen_deltille) => match propogate_constraints(icosahedron, chosen_deltille) {
-//             Ok(_) => println!("remove all options, push this step on the stack and copy a new one"),
-//             Err(_) => println!("remove option from set and try again"),
-//         },
-//         None => println!("discard this step and move back in the stack"),
-//     };
-// }
-
-// pub fn coordinates_with_fewest_possibilities(icosahedron: &Icosahedron) -> Vec<DeltilleSlotId> {
-//     let mut fewest_possibilities_so_far = usize::MAX;
-//     let mut fewest_possibilities_coordinates: Vec<DeltilleSlotId> = Vec::new();
-//     for icoface in coordinates.all_ico_face_coordinates.iter() {
-//         for deltille in
-//             coordinates.all_deltille_coordinates_for_icoface(icosahedron.get_icoface(&icoface))
-//         {
-//             let full_coordinates = DeltilleSlotId {
-//                 icoface: icoface.clone(),
-//                 deltille: deltille.clone(),
-//             };
-//             let len = icosahedron
-//                 .get_deltille_possibilities(&full_coordinates)
-//                 .len();
-//             if len > 0 && len < fewest_possibilities_so_far {
-//                 fewest_possibilities_so_far = len;
-//                 fewest_possibilities_coordinates.clear();
-//                 fewest_possibilities_coordinates.push(full_coordinates);
-//             } else if len > 0 && len == fewest_possibilities_so_far {
-//                 fewest_possibilities_coordinates.push(full_coordinates)
-//             }
-//         }
-//     }
-//     return fewest_possibilities_coordinates;
-// }
-
-// pub fn propogate_constraints(
-//     mut icosahedron: &mut Icosahedron,
-//     chosen_deltille: &DeltilleSlotId,
-// ) -> Result<(), ()> {
-//     return Result::Ok(());
-// }
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::prelude::Resource;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::config_constants::DEFAULT_FREQUENCY;
+use crate::domain::Domain;
+use crate::icosahedron::{
+    ArrayIndex, DeltilleSlotId, Icosahedron, VerticalOrientation, SOCKET_COUNT,
+};
+use crate::net_layout::NetLayout;
+use crate::tileset::DeltilleOptions;
+
+/// entropies closer than this are treated as tied
+const ENTROPY_EPSILON: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepOutcome {
+    /// a deltille slot was collapsed to a single option and constraints propagated cleanly
+    Collapsed(DeltilleSlotId),
+
+    /// a contradiction was found; the choice made at the given deltille slot was undone
+    /// and removed from its options
+    Backtracked(DeltilleSlotId),
+
+    /// the backtrack limit was reached and the solve started over from scratch
+    Restarted,
+
+    /// every deltille slot has exactly one option left
+    Complete,
+
+    /// backtracks and restarts are used up, or no choice is left to undo
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WfcError {
+    /// the solver gave up after `restarts` full restarts
+    Exhausted { restarts: usize },
+}
+
+impl fmt::Display for WfcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WfcError::Exhausted { restarts } => {
+                write!(f, "no solution found after {restarts} restarts")
+            }
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WfcConfig {
+    /// the same seed, tileset, frequency and layout always produce the same tiling
+    pub seed: u64,
+
+    /// deltilles along each icoface edge
+    pub frequency: usize,
+
+    /// where the icofaces sit in the net
+    pub layout: NetLayout,
+
+    /// backtracks allowed within one attempt before starting over
+    pub max_backtracks: usize,
+
+    /// full restarts allowed before giving up
+    pub max_restarts: usize,
+}
+
+impl Default for WfcConfig {
+    fn default() -> Self {
+        return WfcConfig {
+            seed: 0,
+            frequency: DEFAULT_FREQUENCY,
+            layout: NetLayout::Standard,
+            max_backtracks: 1000,
+            max_restarts: 10,
+        };
+    }
+}
+
+/// A choice that can be undone: every slot's options as they were before `choice` was
+/// made at the slot with index `slot`
+pub struct Step {
+    domains: Vec<Domain>,
+    slot: usize,
+    choice: usize,
+}
+
+#[derive(Resource)]
+pub struct WfcState {
+    /// the slots and how they connect, which solving never changes, so states can share
+    /// it and steps only keep the domains
+    pub icosahedron: Arc<Icosahedron>,
+
+    /// the options each slot still allows, by `Icosahedron::slot_index`
+    pub domains: Vec<Domain>,
+
+    pub deltille_options: DeltilleOptions,
+    pub config: WfcConfig,
+    pub steps: Vec<Step>,
+
+    /// backtracks in the current attempt
+    pub backtracks: usize,
+
+    /// backtracks over every attempt
+    pub total_backtracks: usize,
+
+    /// choices or bans that left some slot without options, over every attempt
+    pub contradictions: usize,
+    pub restarts: usize,
+
+    /// indices of the slots whose options the latest step changed, in the order they
+    /// changed; a backtrack restores them in index order
+    pub changed_slots: Vec<usize>,
+
+    /// the slot the latest step chose an option for or, if it backtracked, the slot whose
+    /// choice it undid and banned
+    pub last_choice: Option<usize>,
+
+    /// the slot the latest step's last contradiction left without options
+    pub contradiction: Option<usize>,
+    pub complete: bool,
+    pub failed: bool,
+    initial_domains: Vec<Domain>,
+
+    /// how many of the options across each socket of each slot still allow each of the
+    /// slot's options, by `support_count_index`; kept up to date for the options still in
+    /// the domains and counted afresh whenever the domains are restored
+    support_counts: Vec<u16>,
+
+    /// options of the orientation with the most, the stride of `support_counts`
+    max_options: usize,
+    rng: ChaCha8Rng,
+}
+
+impl WfcState {
+    pub fn new(deltille_options: DeltilleOptions, config: WfcConfig) -> Self {
+        let icosahedron = Icosahedron::with_layout(config.frequency, config.layout);
+        return Self::with_icosahedron(Arc::new(icosahedron), deltille_options, config);
+    }
+
+    /// A state on an icosahedron that is already built, so several states can share one;
+    /// its frequency and layout must be the config's
+    pub fn with_icosahedron(
+        icosahedron: Arc<Icosahedron>,
+        deltille_options: DeltilleOptions,
+        config: WfcConfig,
+    ) -> Self {
+        assert!(
+            icosahedron.frequency == config.frequency && icosahedron.layout == config.layout,
+            "the icosahedron does not match the config's frequency and layout"
+        );
+        let all_options = [
+            Domain::full(deltille_options.deltilles[VerticalOrientation::Up.index()].len()),
+            Domain::full(deltille_options.deltilles[VerticalOrientation::Down.index()].len()),
+        ];
+        let domains: Vec<Domain> = icosahedron
+            .deltille_slots
+            .iter()
+            .map(|slot| all_options[slot.orientation.index()])
+            .collect();
+        let slot_count = icosahedron.deltille_slots.len();
+        let max_options = deltille_options.deltilles.iter().map(Vec::len).max();
+        let max_options = max_options.unwrap_or(0);
+        let mut state = WfcState {
+            icosahedron,
+            initial_domains: domains.clone(),
+            domains,
+            support_counts: vec![0; slot_count * max_options * SOCKET_COUNT],
+            max_options,
+            deltille_options,
+            config,
+            steps: Vec::new(),
+            backtracks: 0,
+            total_backtracks: 0,
+            contradictions: 0,
+            restarts: 0,
+            changed_slots: Vec::new(),
+            last_choice: None,
+            contradiction: None,
+            complete: false,
+            failed: false,
+            rng: ChaCha8Rng::seed_from_u64(config.seed),
+        };
+
+        // rule out options that can never match any neighbor before the first choice
+        let unsupported = state.count_supports(0..slot_count);
+        state.failed = state.has_empty_slot() || state.remove_options(unsupported).is_err();
+        state.initial_domains = state.domains.clone();
+        return state;
+    }
+
+    /// The options a slot still allows
+    pub fn domain(&self, id: &DeltilleSlotId) -> Domain {
+        return self.domains[self.icosahedron.slot_index(id)];
+    }
+
+    /// Collapses the lowest entropy deltille slot and propagates its constraints,
+    /// backtracking if that leads to a contradiction
+    pub fn step(&mut self) -> StepOutcome {
+        if self.complete {
+            return StepOutcome::Complete;
+        }
+        if self.failed {
+            return StepOutcome::Failed;
+        }
+        self.changed_slots.clear();
+        self.last_choice = None;
+        self.contradiction = None;
+        let slot = match self.lowest_entropy_slots().choose(&mut self.rng) {
+            Some(slot) => *slot,
+            None => {
+                self.complete = true;
+                return StepOutcome::Complete;
+            }
+        };
+        self.last_choice = Some(slot);
+
+        let snapshot = self.domains.clone();
+        // domains list their ids in order, so the choice depends only on the seed
+        let options: Vec<usize> = self.domains[slot].iter().collect();
+        let orientation = self.icosahedron.deltille_slots[slot].orientation;
+        let deltilles = &self.deltille_options.deltilles[orientation.index()];
+        let choice = match WeightedIndex::new(options.iter().map(|id| deltilles[*id].weight)) {
+            Ok(weighted) => options[weighted.sample(&mut self.rng)],
+            // no usable weights; fall back to a uniform choice
+            Err(_) => *options.choose(&mut self.rng).unwrap(),
+        };
+        self.steps.push(Step {
+            domains: snapshot,
+            slot,
+            choice,
+        });
+
+        let others = options.into_iter().filter(|id| *id != choice);
+        return match self.remove_options(others.map(|id| (slot, id))) {
+            Ok(()) => StepOutcome::Collapsed(self.icosahedron.slot_id(slot)),
+            Err(empty_slot) => {
+                self.contradictions += 1;
+                self.contradiction = Some(empty_slot);
+                self.backtrack()
+            }
+        };
+    }
+
+    /// Steps until every deltille slot is decided or the solver gives up
+    pub fn run(&mut self) -> Result<(), WfcError> {
+        loop {
+            match self.step() {
+                StepOutcome::Complete => return Ok(()),
+                StepOutcome::Failed => {
+                    return Err(WfcError::Exhausted {
+                        restarts: self.restarts,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Restores the state from before the most recent choice and bans that choice,
+    /// unwinding further while banning leaves the icosahedron contradictory
+    fn backtrack(&mut self) -> StepOutcome {
+        loop {
+            if self.backtracks >= self.config.max_backtracks {
+                return self.restart();
+            }
+            let step = match self.steps.pop() {
+                Some(step) => step,
+                // every choice from the initial state has been ruled out
+                None => {
+                    self.failed = true;
+                    return StepOutcome::Failed;
+                }
+            };
+            self.backtracks += 1;
+            self.total_backtracks += 1;
+            self.last_choice = Some(step.slot);
+            let unsupported = self.restore(step.domains);
+
+            let banned = unsupported.into_iter().chain([(step.slot, step.choice)]);
+            match self.remove_options(banned) {
+                Ok(()) => return StepOutcome::Backtracked(self.icosahedron.slot_id(step.slot)),
+                Err(empty_slot) => {
+                    self.contradictions += 1;
+                    self.contradiction = Some(empty_slot);
+                }
+            }
+        }
+    }
+
+    fn restart(&mut self) -> StepOutcome {
+        if self.restarts >= self.config.max_restarts {
+            self.failed = true;
+            return StepOutcome::Failed;
+        }
+        self.restarts += 1;
+        self.backtracks = 0;
+        self.steps.clear();
+        self.last_choice = None;
+        let unsupported = self.restore(self.initial_domains.clone());
+        debug_assert!(unsupported.is_empty(), "the initial domains are consistent");
+        return StepOutcome::Restarted;
+    }
+
+    fn has_empty_slot(&self) -> bool {
+        return self.domains.iter().any(Domain::is_empty);
+    }
+
+    /// Indices of the undecided slots (more than one option) tied for the lowest Shannon
+    /// entropy
+    fn lowest_entropy_slots(&self) -> Vec<usize> {
+        let mut lowest_entropy_so_far = f32::MAX;
+        let mut lowest_entropy_slots: Vec<usize> = Vec::new();
+        for (slot, domain) in self.domains.iter().enumerate() {
+            if domain.len() < 2 {
+                continue;
+            }
+            let entropy = self.entropy(slot);
+            if entropy < lowest_entropy_so_far - ENTROPY_EPSILON {
+                lowest_entropy_so_far = entropy;
+                lowest_entropy_slots.clear();
+                lowest_entropy_slots.push(slot);
+            } else if entropy <= lowest_entropy_so_far + ENTROPY_EPSILON {
+                lowest_entropy_slots.push(slot);
+            }
+        }
+        return lowest_entropy_slots;
+    }
+
+    /// Shannon entropy of the options left in the slot at `slot`, weighted by
+    /// `Deltille::weight`: `ln(sum(w)) - sum(w * ln(w)) / sum(w)`
+    pub fn entropy(&self, slot: usize) -> f32 {
+        let orientation = self.icosahedron.deltille_slots[slot].orientation;
+        let deltilles = &self.deltille_options.deltilles[orientation.index()];
+        let mut weight_sum = 0.0;
+        let mut weight_log_weight_sum = 0.0;
+        for id in self.domains[slot].iter() {
+            let weight = deltilles[id].weight;
+            if weight > 0.0 {
+                weight_sum += weight;
+                weight_log_weight_sum += weight * weight.ln();
+            }
+        }
+        if weight_sum <= 0.0 {
+            return 0.0;
+        }
+        return weight_sum.ln() - weight_log_weight_sum / weight_sum;
+    }
+
+    /// Notes a change to the slot at `slot` in `changed_slots`, once per run of changes
+    fn mark_changed(&mut self, slot: usize) {
+        if self.changed_slots.last() != Some(&slot) {
+            self.changed_slots.push(slot);
+        }
+    }
+
+    /// Where the support count of option `id` of the slot at `slot` across `socket` is
+    /// kept in `support_counts`
+    fn support_count_index(&self, slot: usize, id: usize, socket: usize) -> usize {
+        return (slot * self.max_options + id) * SOCKET_COUNT + socket;
+    }
+
+    /// Puts back earlier domains and counts supports again where they differ, returning
+    /// the options left without support
+    fn restore(&mut self, domains: Vec<Domain>) -> Vec<(usize, usize)> {
+        // a slot's counts depend on its own domain and its neighbors'
+        let mut recount = vec![false; domains.len()];
+        for (slot, (old, new)) in self.domains.iter().zip(domains.iter()).enumerate() {
+            if old == new {
+                continue;
+            }
+            recount[slot] = true;
+            self.changed_slots.push(slot);
+            for neighbor in self.icosahedron.neighbors[slot].iter() {
+                recount[neighbor.index as usize] = true;
+            }
+        }
+        self.domains = domains;
+        let slots = recount
+            .into_iter()
+            .enumerate()
+            .filter(|(_, recount)| *recount);
+        return self.count_supports(slots.map(|(slot, _)| slot));
+    }
+
+    /// Counts the supports of the options still in the domains of `slots` from scratch,
+    /// and returns the options left without any on some socket
+    fn count_supports(&mut self, slots: impl IntoIterator<Item = usize>) -> Vec<(usize, usize)> {
+        let icosahedron = Arc::clone(&self.icosahedron);
+        let deltille_slots = &icosahedron.deltille_slots;
+        let mut unsupported = Vec::new();
+        for slot in slots {
+            let neighbors = &icosahedron.neighbors[slot];
+            let sockets = deltille_slots[slot].orientation.sockets();
+            for (socket_index, neighbor) in neighbors.iter().enumerate() {
+                let neighbor_index = neighbor.index as usize;
+                let target_socket =
+                    deltille_slots[neighbor_index].orientation.sockets()[neighbor.socket as usize];
+                for id in self.domains[slot].iter() {
+                    let allowed = self.deltille_options.supports.allowed(
+                        sockets[socket_index],
+                        target_socket,
+                        id,
+                    );
+                    let count = (*allowed & self.domains[neighbor_index]).len();
+                    let count_index = self.support_count_index(slot, id, socket_index);
+                    self.support_counts[count_index] = count as u16;
+                    if count == 0 {
+                        unsupported.push((slot, id));
+                    }
+                }
+            }
+        }
+        return unsupported;
+    }
+
+    /// Removes options, given as slot index and option id, then every neighbor option
+    /// that loses its last support on the socket it shares with a removed one, breadth
+    /// first; returns the first slot left without options
+    fn remove_options(
+        &mut self,
+        options: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Result<(), usize> {
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+        for (slot, id) in options {
+            // an option can be unsupported on more than one socket
+            if !self.domains[slot].contains(id) {
+                continue;
+            }
+            self.domains[slot].remove(id);
+            self.mark_changed(slot);
+            if self.domains[slot].is_empty() {
+                return Err(slot);
+            }
+            queue.push_back((slot, id));
+        }
+        let icosahedron = Arc::clone(&self.icosahedron);
+        let slots = &icosahedron.deltille_slots;
+        while let Some((slot, id)) = queue.pop_front() {
+            let sockets = slots[slot].orientation.sockets();
+            for (socket_index, neighbor) in icosahedron.neighbors[slot].iter().enumerate() {
+                let neighbor_index = neighbor.index as usize;
+                let target_socket_index = neighbor.socket as usize;
+                let target_socket =
+                    slots[neighbor_index].orientation.sockets()[target_socket_index];
+                // the neighbor's options that `id` allowed have one support fewer
+                let allowed = self.deltille_options.supports.allowed(
+                    sockets[socket_index],
+                    target_socket,
+                    id,
+                );
+                for target_id in (*allowed & self.domains[neighbor_index]).iter() {
+                    let count_index =
+                        self.support_count_index(neighbor_index, target_id, target_socket_index);
+                    self.support_counts[count_index] -= 1;
+                    if self.support_counts[count_index] > 0 {
+                        continue;
+                    }
+                    self.domains[neighbor_index].remove(target_id);
+                    self.mark_changed(neighbor_index);
+                    if self.domains[neighbor_index].is_empty() {
+                        return Err(neighbor_index);
+                    }
+                    queue.push_back((neighbor_index, target_id));
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use crate::config_constants::DELTILLES_PATH;
+    use crate::icosahedron::{ArrayIndex, Deltille, Icosahedron};
+    use crate::tileset::{DeltilleOptions, SocketRules, Tileset};
+
+    use super::{StepOutcome, WfcConfig, WfcError, WfcState};
+
+    fn deltille(image_path: &str, sockets: [&str; 3]) -> Deltille {
+        weighted_deltille(image_path, sockets, 1.0)
+    }
+
+    fn weighted_deltille(image_path: &str, sockets: [&str; 3], weight: f32) -> Deltille {
+        Deltille {
+            image_path: image_path.to_string(),
+            rotation: 0,
+            flip_x: false,
+            flip_y: false,
+            weight,
+            sockets: sockets.map(String::from),
+        }
+    }
+
+    /// Up deltilles as given, plus each flipped upside down for down slots, without the
+    /// other variants a tileset would add
+    fn deltille_options_from(deltilles: &[Deltille]) -> DeltilleOptions {
+        return deltille_options_with_rules(deltilles, SocketRules::default());
+    }
+
+    fn deltille_options_with_rules(
+        deltilles: &[Deltille],
+        socket_rules: SocketRules,
+    ) -> DeltilleOptions {
+        let down = deltilles
+            .iter()
+            .map(|deltille| {
+                let [ne, s, nw] = deltille.sockets.clone();
+                Deltille {
+                    flip_y: true,
+                    sockets: [s, ne, nw],
+                    ..deltille.clone()
+                }
+            })
+            .collect();
+        return DeltilleOptions::new([deltilles.to_vec(), down], socket_rules);
+    }
+
+    fn assert_all_connections_match(state: &WfcState) {
+        for slot_id in state.icosahedron.deltille_slot_ids() {
+            let slot = state.icosahedron.get_deltille_slot(&slot_id);
+            assert_eq!(state.domain(&slot_id).len(), 1);
+            let option = &state.deltille_options.deltilles[slot.orientation.index()]
+                [state.domain(&slot_id).iter().next().unwrap()];
+            for (socket_index, connection) in slot.connections.iter().enumerate() {
+                let neighbor_id = connection.target_deltille_coordinates;
+                let neighbor = state.icosahedron.get_deltille_slot(&neighbor_id);
+                let neighbor_option = &state.deltille_options.deltilles
+                    [neighbor.orientation.index()]
+                    [state.domain(&neighbor_id).iter().next().unwrap()];
+                assert!(state.deltille_options.socket_rules.matches(
+                    &option.sockets[socket_index],
+                    &neighbor_option.sockets[connection.target_socket.index()]
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn single_deltille_completes() {
+        let mut state = WfcState::new(
+            deltille_options_from(&[deltille("a.png", ["a", "a", "a"])]),
+            WfcConfig::default(),
+        );
+        assert_eq!(state.run(), Ok(()));
+        assert!(state.complete);
+        assert_all_connections_match(&state);
+    }
+
+    #[test]
+    fn incompatible_deltilles_never_touch() {
+        let mut state = WfcState::new(land_and_ocean(), WfcConfig::default());
+        assert_eq!(state.run(), Ok(()));
+        assert_all_connections_match(&state);
+    }
+
+    #[test]
+    fn no_deltilles_fails() {
+        let mut state = WfcState::new(deltille_options_from(&[]), WfcConfig::default());
+        assert_eq!(state.run(), Err(WfcError::Exhausted { restarts: 0 }));
+    }
+
+    #[test]
+    fn unsupported_deltilles_fail_at_construction() {
+        // an up NE socket "a" can never meet a down SW socket "c", so pruning before the
+        // first choice empties every slot
+        let mut state = WfcState::new(
+            deltille_options_from(&[deltille("a.png", ["a", "b", "c"])]),
+            WfcConfig::default(),
+        );
+        assert!(state.failed);
+        assert_eq!(state.run(), Err(WfcError::Exhausted { restarts: 0 }));
+        assert!(state.steps.is_empty());
+        assert_eq!(state.total_backtracks, 0);
+    }
+
+    #[test]
+    fn unsolvable_deltilles_fail_after_backtracking() {
+        // every option has support, but alternating in and out around a pentagon's five
+        // deltilles is impossible, so both choices at the first slot contradict
+        let rules = SocketRules {
+            reversed: BTreeMap::from([("in".to_string(), "out".to_string())]),
+            ..SocketRules::default()
+        };
+        let deltilles = deltille_options_with_rules(
+            &[
+                deltille("in.png", ["in", "in", "in"]),
+                deltille("out.png", ["out", "out", "out"]),
+            ],
+            rules,
+        );
+        let mut state = WfcState::new(deltilles, WfcConfig::default());
+        assert!(!state.failed);
+        assert_eq!(state.run(), Err(WfcError::Exhausted { restarts: 0 }));
+        assert!(state.failed);
+        assert!(state.steps.is_empty());
+        assert_eq!(state.total_backtracks, 1);
+        assert_eq!(state.contradictions, 2);
+    }
+
+    fn land_and_ocean() -> DeltilleOptions {
+        return deltille_options_from(&[
+            deltille("land.png", ["land", "land", "land"]),
+            deltille("ocean.png", ["ocean", "ocean", "ocean"]),
+        ]);
+    }
+
+    #[test]
+    fn backtracking_bans_the_failed_choice() {
+        let mut state = WfcState::new(land_and_ocean(), WfcConfig::default());
+        let StepOutcome::Collapsed(slot_id) = state.step() else {
+            panic!("expected a collapse");
+        };
+        let choice = state.steps.last().unwrap().choice;
+
+        assert_eq!(state.backtrack(), StepOutcome::Backtracked(slot_id));
+        assert_eq!(
+            state.last_choice,
+            Some(state.icosahedron.slot_index(&slot_id))
+        );
+        assert!(state.steps.is_empty());
+        assert_eq!(state.backtracks, 1);
+        assert_eq!(state.total_backtracks, 1);
+        assert!(!state.domain(&slot_id).contains(choice));
+    }
+
+    #[test]
+    fn steps_record_the_propagation() {
+        let mut state = WfcState::new(land_and_ocean(), WfcConfig::default());
+        let StepOutcome::Collapsed(slot_id) = state.step() else {
+            panic!("expected a collapse");
+        };
+        // land and ocean never meet, so one choice decides every slot
+        let chosen = state.icosahedron.slot_index(&slot_id);
+        assert_eq!(state.last_choice, Some(chosen));
+        assert_eq!(state.changed_slots[0], chosen);
+        let mut changed = state.changed_slots.clone();
+        changed.sort();
+        changed.dedup();
+        assert_eq!(changed.len(), state.domains.len());
+        assert_eq!(state.contradiction, None);
+    }
+
+    #[test]
+    fn restarts_are_limited() {
+        let mut state = WfcState::new(
+            land_and_ocean(),
+            WfcConfig {
+                max_backtracks: 0,
+                max_restarts: 1,
+                ..WfcConfig::default()
+            },
+        );
+        state.step();
+        assert_eq!(state.backtrack(), StepOutcome::Restarted);
+        assert_eq!(state.restarts, 1);
+        assert!(state.steps.is_empty());
+        assert!(state.domains.iter().all(|domain| domain.len() == 2));
+
+        state.step();
+        assert_eq!(state.backtrack(), StepOutcome::Failed);
+        assert!(state.failed);
+    }
+
+    fn chosen_deltilles(state: &WfcState) -> Vec<usize> {
+        return state
+            .domains
+            .iter()
+            .map(|domain| domain.iter().next().unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn same_seed_same_tiling() {
+        let config = WfcConfig {
+            seed: 42,
+            ..WfcConfig::default()
+        };
+        let deltilles = deltille_options_from(&[
+            deltille("land.png", ["land", "land", "land"]),
+            deltille("coast.png", ["land", "ocean", "land"]),
+            deltille("ocean.png", ["ocean", "ocean", "ocean"]),
+        ]);
+        let mut first = WfcState::new(deltilles.clone(), config);
+        let mut second = WfcState::new(deltilles, config);
+        assert_eq!(first.run(), Ok(()));
+        assert_eq!(second.run(), Ok(()));
+        assert_eq!(chosen_deltilles(&first), chosen_deltilles(&second));
+    }
+
+    #[test]
+    fn states_share_one_icosahedron() {
+        let icosahedron = Arc::new(Icosahedron::new(WfcConfig::default().frequency));
+        let states: Vec<WfcState> = (0..2)
+            .map(|seed| {
+                let config = WfcConfig {
+                    seed,
+                    ..WfcConfig::default()
+                };
+                WfcState::with_icosahedron(Arc::clone(&icosahedron), land_and_ocean(), config)
+            })
+            .collect();
+        assert_eq!(Arc::strong_count(&icosahedron), 3);
+        for mut state in states {
+            assert_eq!(state.run(), Ok(()));
+            assert_all_connections_match(&state);
+        }
+    }
+
+    #[test]
+    fn entropy_counts_weights() {
+        let uniform = WfcState::new(land_and_ocean(), WfcConfig::default());
+        assert!((uniform.entropy(0) - 2.0_f32.ln()).abs() < 1e-5);
+
+        let skewed = WfcState::new(
+            deltille_options_from(&[
+                weighted_deltille("land.png", ["land", "land", "land"], 9.0),
+                weighted_deltille("ocean.png", ["ocean", "ocean", "ocean"], 1.0),
+            ]),
+            WfcConfig::default(),
+        );
+        assert!(skewed.entropy(0) < uniform.entropy(0));
+    }
+
+    #[test]
+    fn heavy_deltilles_dominate() {
+        let deltilles = deltille_options_from(&[
+            weighted_deltille("land.png", ["land", "land", "land"], 0.0001),
+            weighted_deltille("ocean.png", ["ocean", "ocean", "ocean"], 1000.0),
+        ]);
+        for seed in 0..20 {
+            let config = WfcConfig {
+                seed,
+                ..WfcConfig::default()
+            };
+            let mut state = WfcState::new(deltilles.clone(), config);
+            assert_eq!(state.run(), Ok(()));
+            assert!(chosen_deltilles(&state).iter().all(|id| *id == 1));
+        }
+    }
+
+    #[test]
+    fn directional_sockets_meet_their_reverse() {
+        let reversed = BTreeMap::from([("in".to_string(), "out".to_string())]);
+        let rules = SocketRules {
+            reversed: reversed.clone(),
+            ..SocketRules::default()
+        };
+        let alone = deltille_options_with_rules(&[deltille("in.png", ["in", "in", "in"])], rules);
+        assert!(WfcState::new(alone, WfcConfig::default()).failed);
+
+        // the pentagons make an in/out checkerboard impossible, so plain deltilles fill in
+        let rules = SocketRules {
+            reversed,
+            pairs: vec![
+                ["plain".to_string(), "in".to_string()],
+                ["plain".to_string(), "out".to_string()],
+            ],
+            ..SocketRules::default()
+        };
+        let deltilles = deltille_options_with_rules(
+            &[
+                deltille("in.png", ["in", "in", "in"]),
+                deltille("out.png", ["out", "out", "out"]),
+                deltille("plain.png", ["plain", "plain", "plain"]),
+            ],
+            rules,
+        );
+        let mut state = WfcState::new(deltilles, WfcConfig::default());
+        assert_eq!(state.run(), Ok(()));
+        assert_all_connections_match(&state);
+    }
+
+    #[test]
+    fn wildcard_sockets_match_anything() {
+        let rules = SocketRules {
+            wildcards: vec!["*".to_string()],
+            ..SocketRules::default()
+        };
+        let deltilles = deltille_options_with_rules(
+            &[
+                deltille("a.png", ["a", "b", "c"]),
+                deltille("any.png", ["*", "*", "*"]),
+            ],
+            rules,
+        );
+        let mut state = WfcState::new(deltilles, WfcConfig::default());
+        assert_eq!(state.run(), Ok(()));
+        assert_all_connections_match(&state);
+    }
+
+    #[test]
+    fn bundled_tileset_solves() {
+        let deltilles = Tileset::load(DELTILLES_PATH).unwrap().deltille_options();
+        let mut state = WfcState::new(deltilles, WfcConfig::default());
+        assert_eq!(state.run(), Ok(()));
+        assert_all_connections_match(&state);
+    }
+}